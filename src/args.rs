@@ -1,18 +1,41 @@
 #![warn(clippy::all, clippy::pedantic)]
 #![allow(clippy::too_many_lines)]
-use clap::{Parser, Subcommand};
+use clap::{ArgAction, ArgGroup, Args as ClapArgs, CommandFactory, Parser, Subcommand, ValueEnum};
 use colored::Colorize;
-use std::{io::Write, str::FromStr};
+use futures::StreamExt as _;
+use std::{
+    io::{Read, Write},
+    process::ExitCode,
+    str::FromStr,
+};
+use urlencoding::encode;
 
-use crate::{
-    jenkins::{Jenkins, Tree},
-    job::{self, BuildInfo},
-    node, rec_walk, Result,
+use crate::completion;
+use jenkinsctl::{
+    auth::{self, CredentialProvider},
+    cache::Cache,
+    config::Config,
+    credentials::{self, Secret},
+    human,
+    jenkins::{self, ClientOptions, Jenkins},
+    job, node, plugin,
+    proxy::ProxyOptions,
+    queue,
+    selector::Selector,
+    tls::TlsOptions,
+    url::Tree,
+    user, view, xml, JenkinsClient, Result,
 };
 
 const JENKINS_URL: &str = "JENKINS_URL";
 const JENKINS_USER: &str = "JENKINS_USER";
 const JENKINS_TOKEN: &str = "JENKINS_TOKEN";
+const DRAIN_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+const CONNECT_TIMEOUT: &str = "10s";
+const REQUEST_TIMEOUT: &str = "2m";
+const RETRIES: u32 = 3;
+/// Credential configs fetched at once by `credentials list`.
+const CREDENTIAL_FETCHES: usize = 4;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -35,6 +58,135 @@ struct Args {
         hide_default_value = true
     )]
     token: String,
+    #[arg(
+        long,
+        help = "Config context to use (default: current_context from the config file)",
+        required = false,
+        default_value = "",
+        hide_default_value = true
+    )]
+    context: String,
+    #[arg(
+        long,
+        help = "Read the token from a file (must not be readable by group or others)",
+        required = false,
+        default_value = "",
+        hide_default_value = true
+    )]
+    token_file: String,
+    #[arg(long, value_enum, help = "Authentication mode [default: basic]")]
+    auth: Option<auth::AuthMode>,
+    #[arg(
+        short = 'H',
+        long = "header",
+        value_name = "HEADER",
+        help = "Extra header sent with every request (format: 'Name: value')"
+    )]
+    headers: Vec<String>,
+    #[arg(
+        long,
+        help = "PEM bundle of CA certificates to trust in addition to the system ones",
+        required = false,
+        default_value = "",
+        hide_default_value = true
+    )]
+    ca_cert: String,
+    #[arg(
+        long,
+        help = "PEM client certificate for mutual TLS",
+        required = false,
+        default_value = "",
+        hide_default_value = true,
+        requires = "client_key"
+    )]
+    client_cert: String,
+    #[arg(
+        long,
+        help = "PEM private key of the client certificate",
+        required = false,
+        default_value = "",
+        hide_default_value = true,
+        requires = "client_cert"
+    )]
+    client_key: String,
+    #[arg(long, help = "Do not verify the server certificate (insecure)")]
+    insecure_skip_verify: bool,
+    #[arg(
+        long,
+        help = "Proxy for all requests (default: HTTPS_PROXY/HTTP_PROXY)",
+        required = false,
+        default_value = "",
+        hide_default_value = true
+    )]
+    proxy: String,
+    #[arg(
+        long,
+        help = "Comma-separated hosts reached without the proxy (default: NO_PROXY)",
+        required = false,
+        default_value = "",
+        hide_default_value = true
+    )]
+    no_proxy: String,
+    #[arg(
+        long,
+        value_name = "DURATION",
+        help = "Time allowed to connect [default: 10s]",
+        value_parser = human::parse_duration
+    )]
+    connect_timeout: Option<u64>,
+    #[arg(
+        long,
+        value_name = "DURATION",
+        help = "Time allowed to wait for a response, then for each part of its body, 0s waits forever [default: 2m]",
+        value_parser = human::parse_duration
+    )]
+    timeout: Option<u64>,
+    #[arg(
+        long,
+        help = "Retries of GET requests failing with 502/503/504 or a reset connection [default: 3]"
+    )]
+    retries: Option<u32>,
+    #[arg(
+        long,
+        value_name = "RPS",
+        help = "Maximum number of requests per second"
+    )]
+    rate_limit: Option<f64>,
+    #[arg(
+        long,
+        value_name = "DURATION",
+        help = "Cache the job tree and the node list on disk for this long",
+        value_parser = human::parse_duration
+    )]
+    cache_ttl: Option<u64>,
+    #[arg(long, help = "Fetch the cached job tree and node list again")]
+    refresh: bool,
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Save the requests and responses to a directory (credentials are redacted)",
+        required = false,
+        default_value = "",
+        hide_default_value = true,
+        conflicts_with = "replay"
+    )]
+    record: String,
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Serve the responses from a recording instead of the network",
+        required = false,
+        default_value = "",
+        hide_default_value = true
+    )]
+    replay: String,
+    #[arg(
+        short,
+        long,
+        action = ArgAction::Count,
+        help = "Trace the requests: -v with their status and latency, -vv the headers, -vvv the bodies"
+    )]
+    verbose: u8,
     #[command(subcommand)]
     commands: Commands,
 }
@@ -72,12 +224,70 @@ enum Commands {
         #[command(subcommand)]
         job_commands: JobAction,
     },
+    #[command(about = "Cluster-wide build actions")]
+    #[command(arg_required_else_help(true))]
+    Builds {
+        #[command(subcommand)]
+        builds_commands: BuildsAction,
+    },
+    #[command(about = "Build queue actions")]
+    #[command(arg_required_else_help(true))]
+    Queue {
+        #[command(subcommand)]
+        queue_commands: QueueAction,
+    },
+    #[command(about = "Plugin actions")]
+    #[command(arg_required_else_help(true))]
+    Plugin {
+        #[command(subcommand)]
+        plugin_commands: PluginAction,
+    },
+    #[command(about = "Script console actions")]
+    #[command(arg_required_else_help(true))]
+    Script {
+        #[command(subcommand)]
+        script_commands: ScriptAction,
+    },
+    #[command(about = "Credentials store actions")]
+    #[command(arg_required_else_help(true))]
+    Credentials {
+        #[command(subcommand)]
+        credentials_commands: CredentialsAction,
+    },
+    #[command(about = "View actions")]
+    #[command(arg_required_else_help(true))]
+    View {
+        #[command(subcommand)]
+        view_commands: ViewAction,
+    },
+    #[command(about = "API token actions")]
+    #[command(arg_required_else_help(true))]
+    Token {
+        #[command(subcommand)]
+        token_commands: TokenAction,
+    },
+    #[command(about = "Show the authenticated user and its authorities")]
+    Whoami,
     #[command(about = "Display system-wide information")]
     Info,
+    #[command(
+        about = "Print a shell completion script (e.g. source <(jenkinsctl completion bash))"
+    )]
+    Completion {
+        #[arg(index = 1, value_enum, help = "Shell")]
+        shell: completion::Shell,
+    },
+    #[command(name = "complete-names", hide = true)]
+    Complete {
+        #[arg(index = 1)]
+        index: usize,
+        #[arg(index = 2, trailing_var_arg = true, allow_hyphen_values = true)]
+        words: Vec<String>,
+    },
 }
 
 #[derive(Subcommand)]
-pub enum ShutdownState {
+enum ShutdownState {
     #[command(about = "Set shutdown banner")]
     On {
         #[arg(
@@ -94,7 +304,7 @@ pub enum ShutdownState {
 }
 
 #[derive(Subcommand)]
-pub enum CopyItem {
+enum CopyItem {
     #[command(about = "Make a copy of specific job")]
     Job,
     #[command(about = "Make a copy of specific view")]
@@ -104,22 +314,281 @@ pub enum CopyItem {
 #[derive(Subcommand)]
 enum NodeAction {
     #[command(about = "Show node information")]
+    #[command(arg_required_else_help(true), args_conflicts_with_subcommands(true))]
     Show {
+        #[arg(
+            index = 1,
+            help = "Node name",
+            required = false,
+            default_value = "",
+            hide_default_value = true
+        )]
+        node: String,
+        #[command(flatten)]
+        thresholds: Thresholds,
         #[command(subcommand)]
-        show_commands: ShowAction,
+        show_commands: Option<ShowAction>,
+    },
+    #[command(about = "Show health of all nodes")]
+    Health {
+        #[command(flatten)]
+        thresholds: Thresholds,
+        #[arg(long, help = "Exit with status 1 if any node has a warning")]
+        fail_on_warning: bool,
+        #[arg(
+            long,
+            help = "Select nodes by a label expression (e.g. 'label=linux && !gpu')"
+        )]
+        selector: Option<Selector>,
     },
     #[command(aliases = ["ls"], about = "List all nodes")]
     List {
-        #[arg(short, long, help = "Show node offline")]
+        #[arg(short, long, help = "Show node status and offline cause")]
         status: bool,
+        #[arg(long, help = "Show offline nodes only (implies --status)")]
+        offline_only: bool,
+        #[arg(
+            long,
+            help = "Select nodes by a label expression (e.g. 'label=linux && !gpu')"
+        )]
+        selector: Option<Selector>,
     },
     #[command(about = "Switch node state")]
     Set {
-        #[arg(index = 1, help = "Node name")]
+        #[arg(
+            index = 1,
+            help = "Node name",
+            required_unless_present = "selector",
+            conflicts_with = "selector",
+            default_value = "",
+            hide_default_value = true
+        )]
         node: String,
+        #[arg(
+            long,
+            help = "Select nodes by a label expression (e.g. 'label=linux && !gpu')"
+        )]
+        selector: Option<Selector>,
         #[command(subcommand)]
         state: NodeState,
     },
+    #[command(about = "Create a permanent agent")]
+    Create {
+        #[arg(
+            index = 1,
+            help = "Node name",
+            required_unless_present = "file",
+            conflicts_with = "file",
+            default_value = "",
+            hide_default_value = true
+        )]
+        node: String,
+        #[arg(
+            short,
+            long,
+            help = "Create the node from a config.xml (use '-' to read from stdin)"
+        )]
+        file: Option<String>,
+        #[arg(long, help = "Number of executors", default_value = "1")]
+        executors: u32,
+        #[arg(
+            long,
+            help = "Space or comma separated list of labels",
+            default_value = "",
+            hide_default_value = true
+        )]
+        labels: String,
+        #[arg(
+            long,
+            help = "Remote root directory",
+            required_unless_present = "file",
+            default_value = "",
+            hide_default_value = true
+        )]
+        remote_fs: String,
+        #[arg(
+            long,
+            help = "Node description",
+            default_value = "",
+            hide_default_value = true
+        )]
+        description: String,
+        #[arg(long, help = "Launch method", value_enum, default_value = "jnlp")]
+        launcher: Launcher,
+        #[arg(
+            long,
+            help = "Host to connect to (ssh launcher)",
+            required_if_eq("launcher", "ssh"),
+            default_value = "",
+            hide_default_value = true
+        )]
+        host: String,
+        #[arg(long, help = "Port to connect to (ssh launcher)", default_value = "22")]
+        port: u16,
+        #[arg(
+            long,
+            help = "Credentials id (ssh launcher)",
+            required_if_eq("launcher", "ssh"),
+            default_value = "",
+            hide_default_value = true
+        )]
+        credentials_id: String,
+    },
+    #[command(
+        aliases = ["rm", "del"],
+        about = "Delete a node (use with caution, the action is permanent)"
+    )]
+    Delete {
+        #[arg(
+            index = 1,
+            help = "Node name",
+            required_unless_present = "selector",
+            conflicts_with = "selector",
+            default_value = "",
+            hide_default_value = true
+        )]
+        node: String,
+        #[arg(
+            long,
+            help = "Select nodes by a label expression (e.g. 'label=linux && !gpu')"
+        )]
+        selector: Option<Selector>,
+        #[arg(short, long, help = "Do not ask for confirmation")]
+        yes: bool,
+    },
+    #[command(about = "Get or apply node config.xml")]
+    Config {
+        #[command(subcommand)]
+        config_commands: ConfigAction,
+    },
+    #[command(about = "Add or remove node labels")]
+    Labels {
+        #[command(subcommand)]
+        labels_commands: LabelsAction,
+    },
+    #[command(about = "Take a node offline and wait until its executors are idle")]
+    Drain {
+        #[arg(
+            index = 1,
+            help = "Node name",
+            required_unless_present = "selector",
+            conflicts_with = "selector",
+            default_value = "",
+            hide_default_value = true
+        )]
+        node: String,
+        #[arg(
+            long,
+            help = "Select nodes by a label expression (e.g. 'label=linux && !gpu')"
+        )]
+        selector: Option<Selector>,
+        #[arg(
+            short,
+            long,
+            help = "Offline reason",
+            default_value = "Drained by jenkinsctl"
+        )]
+        reason: String,
+        #[arg(
+            long,
+            help = "Give up waiting after a duration (format: 90s, 15m, 2h, 1h30m)",
+            value_parser = human::parse_duration
+        )]
+        timeout: Option<u64>,
+        #[arg(long, help = "Disconnect the node once it is drained")]
+        disconnect: bool,
+    },
+}
+
+#[derive(ClapArgs)]
+struct Thresholds {
+    #[arg(
+        long,
+        help = "Warn when free disk space is below a size",
+        default_value = "10G",
+        value_parser = human::parse_size
+    )]
+    min_disk: u64,
+    #[arg(
+        long,
+        help = "Warn when free temp space is below a size",
+        default_value = "1G",
+        value_parser = human::parse_size
+    )]
+    min_temp: u64,
+    #[arg(
+        long,
+        help = "Warn when available memory is below a size",
+        default_value = "256M",
+        value_parser = human::parse_size
+    )]
+    min_memory: u64,
+    #[arg(
+        long,
+        help = "Warn when the clock drift is over a duration",
+        default_value = "5s",
+        value_parser = human::parse_duration
+    )]
+    max_clock_drift: u64,
+    #[arg(
+        long,
+        help = "Warn when the response time is over a duration",
+        default_value = "5s",
+        value_parser = human::parse_duration
+    )]
+    max_response_time: u64,
+}
+
+impl From<Thresholds> for node::Thresholds {
+    fn from(t: Thresholds) -> Self {
+        Self {
+            min_disk: t.min_disk,
+            min_temp: t.min_temp,
+            min_memory: t.min_memory,
+            max_clock_drift: t.max_clock_drift,
+            max_response_time: t.max_response_time,
+        }
+    }
+}
+
+#[derive(Clone, ValueEnum)]
+enum Launcher {
+    Jnlp,
+    Ssh,
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    #[command(about = "Print config.xml")]
+    Get {
+        #[arg(index = 1, help = "Item name")]
+        name: String,
+    },
+    #[command(about = "Replace config.xml")]
+    Apply {
+        #[arg(index = 1, help = "Item name")]
+        name: String,
+        #[arg(index = 2, help = "Config file (use '-' to read from stdin)")]
+        file: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum LabelsAction {
+    #[command(about = "Add a label to a node")]
+    Add {
+        #[arg(index = 1, help = "Node name")]
+        node: String,
+        #[arg(index = 2, help = "Label")]
+        label: String,
+    },
+    #[command(about = "Remove a label from a node")]
+    Remove {
+        #[arg(index = 1, help = "Node name")]
+        node: String,
+        #[arg(index = 2, help = "Label")]
+        label: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -230,8 +699,8 @@ enum BuildItem {
     Log,
 }
 
-#[derive(Subcommand)]
-pub enum NodeState {
+#[derive(Clone, Subcommand)]
+enum NodeState {
     #[command(about = "Disconnect a node")]
     Disconnect {
         #[arg(
@@ -260,16 +729,445 @@ pub enum NodeState {
     Online,
 }
 
-enum BuildParam {
-    Range(u64, u64),
-    Once(u64),
+#[derive(Subcommand)]
+enum BuildsAction {
+    #[command(about = "List builds running on all nodes")]
+    Running {
+        #[arg(
+            long,
+            help = "Show builds of a specific node only",
+            required = false,
+            default_value = "",
+            hide_default_value = true
+        )]
+        node: String,
+        #[arg(
+            long,
+            help = "Show builds running longer than a duration (format: 90s, 15m, 2h, 1h30m)",
+            value_parser = human::parse_duration
+        )]
+        longer_than: Option<u64>,
+        #[arg(long, help = "Interrupt the selected builds")]
+        kill: bool,
+        #[arg(
+            short,
+            long,
+            help = "Signal used with --kill (HUP, TERM, KILL)",
+            default_value = "TERM"
+        )]
+        signal: String,
+    },
 }
 
-impl FromStr for BuildParam {
-    type Err = std::num::ParseIntError;
-
-    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
-        if s.contains("..") & !s.contains("..=") {
+#[derive(Subcommand)]
+enum QueueAction {
+    #[command(aliases = ["ls"], about = "List queued items")]
+    List,
+    #[command(about = "Cancel queued item(s)")]
+    #[command(group(ArgGroup::new("target").required(true).args(["id", "job", "all_stuck"])))]
+    Cancel {
+        #[arg(index = 1, help = "Queue item id")]
+        id: Option<u64>,
+        #[arg(
+            long,
+            help = "Cancel all queued items of a job (format: path/to/jenkins/job)"
+        )]
+        job: Option<String>,
+        #[arg(long, help = "Cancel all stuck items")]
+        all_stuck: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum PluginAction {
+    #[command(aliases = ["ls"], about = "List installed plugins")]
+    List {
+        #[arg(long, help = "Show plugins with an available update only")]
+        updates: bool,
+    },
+    #[command(about = "Install plugin(s), a restart may be required afterwards")]
+    Install {
+        #[arg(
+            index = 1,
+            help = "Plugin(s) to install (format: name[@version])",
+            required = true
+        )]
+        plugins: Vec<String>,
+    },
+    #[command(about = "Uninstall a plugin (takes effect after a restart)")]
+    Uninstall {
+        #[arg(index = 1, help = "Plugin name")]
+        name: String,
+        #[arg(short, long, help = "Do not ask for confirmation")]
+        yes: bool,
+    },
+    #[command(about = "Enable a plugin (takes effect after a restart)")]
+    Enable {
+        #[arg(index = 1, help = "Plugin name")]
+        name: String,
+    },
+    #[command(about = "Disable a plugin (takes effect after a restart)")]
+    Disable {
+        #[arg(index = 1, help = "Plugin name")]
+        name: String,
+    },
+    #[command(about = "Print installed plugins in the jenkins-plugin-cli format")]
+    Export,
+}
+
+#[derive(ClapArgs)]
+struct CredentialsStore {
+    #[arg(
+        long,
+        help = "Use the store of a folder (format: path/to/jenkins/folder)",
+        default_value = "",
+        hide_default_value = true
+    )]
+    folder: String,
+    #[arg(long, help = "Credentials domain", default_value = "_")]
+    domain: String,
+}
+
+impl CredentialsStore {
+    /// `path` relative to the domain of the store.
+    fn tree(&self, path: &str) -> Tree {
+        let store = if self.folder.is_empty() {
+            "system"
+        } else {
+            "folder"
+        };
+
+        Tree::new(format!("credentials/store/{store}/domain"))
+            .build_path(&self.folder)
+            .segment(&self.domain)
+            .append(path)
+    }
+
+    /// `path` relative to the credential `id` of the store.
+    fn credential(&self, id: &str, path: &str) -> Tree {
+        self.tree("credential").segment(id).append(path)
+    }
+}
+
+#[derive(Clone, ValueEnum)]
+enum CredentialKind {
+    UsernamePassword,
+    SecretText,
+    SshKey,
+    SecretFile,
+}
+
+#[derive(Clone, ValueEnum)]
+enum CredentialScope {
+    Global,
+    System,
+}
+
+#[derive(ClapArgs)]
+struct CredentialSpec {
+    #[arg(index = 1, help = "Credentials id")]
+    id: String,
+    #[arg(
+        long = "type",
+        value_name = "TYPE",
+        help = "Credentials type",
+        value_enum
+    )]
+    kind: CredentialKind,
+    #[arg(
+        long,
+        help = "Username (username-password and ssh-key types)",
+        required_if_eq_any([("kind", "username-password"), ("kind", "ssh-key")]),
+        default_value = "",
+        hide_default_value = true
+    )]
+    username: String,
+    #[arg(
+        long,
+        help = "Read the secret from a file instead of stdin",
+        default_value = "-",
+        hide_default_value = true
+    )]
+    secret_file: String,
+    #[arg(
+        long,
+        help = "File name stored with a secret-file credential",
+        default_value = "",
+        hide_default_value = true
+    )]
+    file_name: String,
+    #[arg(
+        long,
+        help = "Description",
+        default_value = "",
+        hide_default_value = true
+    )]
+    description: String,
+    #[arg(long, help = "Credentials scope", value_enum, default_value = "global")]
+    scope: CredentialScope,
+    #[command(flatten)]
+    store: CredentialsStore,
+}
+
+impl CredentialSpec {
+    /// Read the secret (never taken from the command line) and render the
+    /// credential config.xml.
+    fn config_xml(&self) -> Result<String> {
+        let data = if self.secret_file == "-" {
+            let mut data = Vec::new();
+            std::io::stdin().read_to_end(&mut data)?;
+            data
+        } else {
+            std::fs::read(&self.secret_file)?
+        };
+        if !matches!(self.kind, CredentialKind::SecretFile)
+            && data.iter().all(u8::is_ascii_whitespace)
+        {
+            return Err(if self.secret_file == "-" {
+                "the secret read from stdin is empty".into()
+            } else {
+                format!("the secret in {} is empty", self.secret_file).into()
+            });
+        }
+
+        let secret = match self.kind {
+            CredentialKind::UsernamePassword => Secret::UsernamePassword {
+                username: self.username.clone(),
+                password: String::from_utf8(data)?.trim_end_matches('\n').to_string(),
+            },
+            CredentialKind::SecretText => {
+                Secret::Text(String::from_utf8(data)?.trim_end_matches('\n').to_string())
+            }
+            CredentialKind::SshKey => Secret::SshKey {
+                username: self.username.clone(),
+                private_key: String::from_utf8(data)?,
+            },
+            CredentialKind::SecretFile => {
+                let file_name = if self.file_name.is_empty() {
+                    std::path::Path::new(&self.secret_file)
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .filter(|_| self.secret_file != "-")
+                        .ok_or("--file-name is required when the secret is read from stdin")?
+                        .to_string()
+                } else {
+                    self.file_name.clone()
+                };
+                Secret::File { file_name, data }
+            }
+        };
+        let scope = match self.scope {
+            CredentialScope::Global => "GLOBAL",
+            CredentialScope::System => "SYSTEM",
+        };
+
+        Ok(credentials::config_xml(
+            &self.id,
+            scope,
+            &self.description,
+            &secret,
+        ))
+    }
+}
+
+#[derive(Subcommand)]
+enum CredentialsAction {
+    #[command(aliases = ["ls"], about = "List credentials (secrets are never shown)")]
+    List {
+        #[command(flatten)]
+        store: CredentialsStore,
+    },
+    #[command(about = "Create credentials, the secret is read from stdin or --secret-file")]
+    Create {
+        #[command(flatten)]
+        spec: CredentialSpec,
+    },
+    #[command(about = "Update credentials, the secret is read from stdin or --secret-file")]
+    Update {
+        #[command(flatten)]
+        spec: CredentialSpec,
+    },
+    #[command(aliases = ["rm", "del"], about = "Delete credentials")]
+    Delete {
+        #[arg(index = 1, help = "Credentials id")]
+        id: String,
+        #[command(flatten)]
+        store: CredentialsStore,
+        #[arg(short, long, help = "Do not ask for confirmation")]
+        yes: bool,
+    },
+}
+
+#[derive(ClapArgs)]
+struct ViewOwner {
+    #[arg(
+        long,
+        global = true,
+        help = "Use the views of a folder (format: path/to/jenkins/folder)",
+        default_value = "",
+        hide_default_value = true
+    )]
+    folder: String,
+}
+
+impl ViewOwner {
+    fn tree(&self, path: String) -> Tree {
+        Tree::new(path).build_path(&self.folder)
+    }
+
+    /// A resource of the view `name`, which may contain any character.
+    fn view(&self, name: &str, path: &str) -> Tree {
+        Tree::new("view".to_string())
+            .segment(name)
+            .append(path)
+            .build_path(&self.folder)
+    }
+}
+
+#[derive(Clone, ValueEnum)]
+enum ViewType {
+    List,
+    My,
+}
+
+#[derive(Subcommand)]
+enum ViewAction {
+    #[command(aliases = ["ls"], about = "List views")]
+    List {
+        #[command(flatten)]
+        owner: ViewOwner,
+    },
+    #[command(about = "Show jobs of a view and their status")]
+    Show {
+        #[arg(index = 1, help = "View name")]
+        view: String,
+        #[command(flatten)]
+        owner: ViewOwner,
+    },
+    #[command(about = "Create a view")]
+    Create {
+        #[arg(index = 1, help = "View name")]
+        name: String,
+        #[arg(
+            long = "type",
+            value_name = "TYPE",
+            help = "View type",
+            value_enum,
+            default_value = "list"
+        )]
+        kind: ViewType,
+        #[arg(
+            long,
+            help = "Include jobs matching a regular expression (list view)",
+            default_value = "",
+            hide_default_value = true
+        )]
+        regex: String,
+        #[arg(long, help = "Include jobs of nested folders (list view)")]
+        recurse: bool,
+        #[command(flatten)]
+        owner: ViewOwner,
+    },
+    #[command(about = "Add a job to a list view")]
+    AddJob {
+        #[arg(index = 1, help = "View name")]
+        view: String,
+        #[arg(index = 2, help = "Job name (relative to the view owner)")]
+        job: String,
+        #[command(flatten)]
+        owner: ViewOwner,
+    },
+    #[command(about = "Remove a job from a list view")]
+    RemoveJob {
+        #[arg(index = 1, help = "View name")]
+        view: String,
+        #[arg(index = 2, help = "Job name (relative to the view owner)")]
+        job: String,
+        #[command(flatten)]
+        owner: ViewOwner,
+    },
+    #[command(about = "Get or apply view config.xml")]
+    Config {
+        #[command(subcommand)]
+        config_commands: ConfigAction,
+        #[command(flatten)]
+        owner: ViewOwner,
+    },
+    #[command(aliases = ["rm", "del"], about = "Delete a view")]
+    Delete {
+        #[arg(index = 1, help = "View name")]
+        view: String,
+        #[command(flatten)]
+        owner: ViewOwner,
+        #[arg(short, long, help = "Do not ask for confirmation")]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TokenAction {
+    #[command(about = "Generate a new API token (the value is shown only once)")]
+    Create {
+        #[arg(index = 1, help = "Token name")]
+        name: String,
+        #[arg(long, help = "Save the token into the active config context")]
+        save: bool,
+    },
+    #[command(
+        aliases = ["ls"],
+        about = "List API tokens of the current user (runs a script in the script console, \
+                 requires Overall/Administer)"
+    )]
+    List,
+    #[command(about = "Revoke an API token")]
+    Revoke {
+        #[arg(index = 1, help = "Token uuid")]
+        uuid: String,
+        #[arg(short, long, help = "Do not ask for confirmation")]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ScriptAction {
+    #[command(
+        about = "Run a Groovy script in the script console (destructive, asks for confirmation)"
+    )]
+    Run {
+        #[arg(
+            index = 1,
+            help = "Script file (use '-' to read from stdin)",
+            required_unless_present = "expr",
+            conflicts_with = "expr",
+            default_value = "",
+            hide_default_value = true
+        )]
+        file: String,
+        #[arg(short, long, help = "Evaluate an inline Groovy expression")]
+        expr: Option<String>,
+        #[arg(
+            long,
+            help = "Run the script on a specific node",
+            required = false,
+            default_value = "",
+            hide_default_value = true
+        )]
+        node: String,
+        #[arg(short, long, help = "Do not ask for confirmation")]
+        yes: bool,
+    },
+}
+
+enum BuildParam {
+    Range(u64, u64),
+    Once(u64),
+}
+
+impl FromStr for BuildParam {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if s.contains("..") & !s.contains("..=") {
             let start = s.split_once('.').unwrap().0.parse::<u64>()?;
             let end = s.rsplit_once('.').unwrap().1.parse::<u64>()?;
 
@@ -285,37 +1183,376 @@ impl FromStr for BuildParam {
     }
 }
 
-pub async fn handle() -> Result<()> {
+/// A resource of the node `node`, which may contain any character.
+fn node_tree(node: &str, path: &str) -> Tree {
+    Tree::new("computer".to_string()).segment(node).append(path)
+}
+
+async fn computer(jenkins: &Jenkins, node: &str) -> Result<node::Computer> {
+    let tree = node_tree(node, "api/json");
+    let json_data = jenkins.get_json_data(&tree).await?;
+
+    Jenkins::system::<node::Computer>(json_data.get_ref().as_slice())
+}
+
+async fn select_nodes(client: &JenkinsClient, selector: &Selector) -> Result<Vec<String>> {
+    Ok(client
+        .node_labels()
+        .await?
+        .into_iter()
+        .filter(|n| selector.matches(&n.name, &n.labels()))
+        .map(|n| n.name)
+        .collect())
+}
+
+/// Run an action on every node concurrently. A single node (selected by
+/// name) reports errors as is, a selection of nodes gets a summary table.
+async fn on_nodes<F, Fut>(nodes: &[String], summary: bool, action: F) -> Result<()>
+where
+    F: Fn(String) -> Fut,
+    Fut: std::future::Future<Output = Result<String>>,
+{
+    if !summary {
+        for node in nodes {
+            log::info!("{}", action(node.clone()).await?);
+        }
+        return Ok(());
+    }
+
+    if nodes.is_empty() {
+        log::warn!("no nodes match the selector");
+        return Ok(());
+    }
+
+    let results = futures::future::join_all(nodes.iter().cloned().map(action)).await;
+
+    let mut failed = 0;
+    for (node, result) in nodes.iter().zip(results) {
+        match result {
+            Ok(message) => println!("{node:.<40}{:<8}{message}", "ok".green()),
+            Err(e) => {
+                failed += 1;
+                println!("{node:.<40}{:<8}{e}", "failed".red());
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!("{failed} of {} node operations failed", nodes.len()).into());
+    }
+
+    Ok(())
+}
+
+async fn set_node(jenkins: &Jenkins, node: String, state: NodeState) -> Result<String> {
+    let temporarily_offline = computer(jenkins, &node).await?.temporarily_offline;
+
+    let message = match state {
+        NodeState::Offline { .. } if temporarily_offline => {
+            return Ok(format!("node {node} is already offline"));
+        }
+        NodeState::Online if !temporarily_offline => {
+            return Ok(format!("node {node} is already online"));
+        }
+        NodeState::Disconnect { .. } => format!("node {node} disconnected"),
+        NodeState::Connect => format!("node {node} connected"),
+        NodeState::Offline { .. } => format!("node {node} taken offline"),
+        NodeState::Online => format!("node {node} brought online"),
+    };
+
+    let tree = node_tree(&node, "");
+    let (res, what) = match state {
+        NodeState::Disconnect { reason } => {
+            (jenkins.disconnect(&tree, &reason).await?, "disconnect")
+        }
+        NodeState::Connect => (jenkins.connect(&tree).await?, "connect"),
+        NodeState::Offline { reason } => (
+            jenkins.toggle_offline(&tree, &reason).await?,
+            "take offline",
+        ),
+        NodeState::Online => (jenkins.toggle_offline(&tree, "").await?, "bring online"),
+    };
+    jenkins::accepted(res, &format!("{what} node {node}"))?;
+
+    Ok(message)
+}
+
+async fn drain_node(
+    jenkins: &Jenkins,
+    node: String,
+    reason: String,
+    timeout: Option<u64>,
+    disconnect: bool,
+) -> Result<String> {
+    let tree = node_tree(&node, "");
+
+    if !computer(jenkins, &node).await?.temporarily_offline {
+        log::info!("taking node {node} offline");
+        let res = jenkins.toggle_offline(&tree, &reason).await?;
+        jenkins::accepted(res, &format!("take offline node {node}"))?;
+    }
+
+    let started = std::time::Instant::now();
+    while !computer(jenkins, &node).await?.idle {
+        if let Some(timeout) = timeout {
+            if started.elapsed() >= std::time::Duration::from_millis(timeout) {
+                return Err(format!(
+                    "node {node} did not drain within {}",
+                    human::duration(timeout)
+                )
+                .into());
+            }
+        }
+        log::info!("waiting for node {node} executors to become idle");
+        tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+    }
+
+    if disconnect {
+        let res = jenkins.disconnect(&tree, &reason).await?;
+        jenkins::accepted(res, &format!("disconnect node {node}"))?;
+        return Ok(format!("node {node} is drained and disconnected"));
+    }
+
+    Ok(format!("node {node} is drained"))
+}
+
+/// Name the closest job paths in the error of an operation on a job that
+/// does not exist.
+async fn suggest_jobs<T>(client: &JenkinsClient, job: &str, res: Result<T>) -> Result<T> {
+    let Err(e) = res else {
+        return res;
+    };
+
+    match client.similar_jobs(job).await {
+        Ok(Some(similar)) if similar.is_empty() => Err(format!("{e}: job {job} not found").into()),
+        Ok(Some(similar)) => Err(format!(
+            "{e}: job {job} not found, did you mean {}?",
+            similar.join(", ")
+        )
+        .into()),
+        _ => Err(e),
+    }
+}
+
+/// Print the console output of a build until it is finished.
+async fn follow_console(client: &JenkinsClient, job: &str, build: u32) -> Result<()> {
+    let console = client.console(job, build);
+    futures::pin_mut!(console);
+
+    let mut out = std::io::stdout();
+    while let Some(chunk) = futures::TryStreamExt::try_next(&mut console).await? {
+        out.write_all(&chunk)?;
+        out.flush()?;
+    }
+
+    Ok(())
+}
+
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{home}/{rest}"),
+        _ => path.to_string(),
+    }
+}
+
+fn read_input(path: &str) -> Result<String> {
+    if path == "-" {
+        let mut input = String::new();
+        std::io::stdin().read_to_string(&mut input)?;
+        return Ok(input);
+    }
+
+    Ok(std::fs::read_to_string(path)?)
+}
+
+fn confirm(prompt: &str) -> Result<bool> {
+    print!("{prompt} [y/N] ");
+    std::io::stdout().flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+pub async fn handle() -> Result<ExitCode> {
     let args = Args::parse();
+
+    let mut logger = pretty_env_logger::formatted_builder();
+    logger.filter_level(log::LevelFilter::Error);
+    if let Ok(filters) = std::env::var("RUST_LOG") {
+        logger.parse_filters(&filters);
+    }
+    if args.verbose > 0 {
+        logger.filter_module("jenkinsctl::trace", log::LevelFilter::Info);
+    }
+    logger.init();
+
+    match &args.commands {
+        Commands::Completion { shell } => {
+            completion::print(*shell, Args::command());
+            return Ok(ExitCode::SUCCESS);
+        }
+        // Other words are completed by the shell, without the controller
+        Commands::Complete { index, words }
+            if completion::target(Args::command(), words, *index).is_none() =>
+        {
+            return Ok(ExitCode::FAILURE);
+        }
+        _ => {}
+    }
+
     let url = std::env::var(JENKINS_URL);
     let user = std::env::var(JENKINS_USER);
     let token = std::env::var(JENKINS_TOKEN);
 
-    let url = if let Ok(v) = url { v } else { args.url };
-    let user = if let Ok(v) = user { v } else { args.user };
-    let token = if let Ok(v) = token { v } else { args.token };
-
-    if url.is_empty() || user.is_empty() || token.is_empty() {
-        log::error!(
-            "missing argument(s): url={}, user={}, token={}",
-            !url.is_empty(),
-            !user.is_empty(),
-            !token.is_empty()
-        );
-        std::process::exit(1);
+    let config = Config::load()?;
+    let context = config.context(&args.context)?;
+
+    let url = match url {
+        Ok(v) => v,
+        Err(_) if args.url.is_empty() => context.url,
+        Err(_) => args.url,
+    };
+    let user = match user {
+        Ok(v) => v,
+        Err(_) if args.user.is_empty() => context.user,
+        Err(_) => args.user,
+    };
+    let token = match token {
+        Ok(v) => v,
+        Err(_) if args.token.is_empty() => context.token,
+        Err(_) => args.token,
+    };
+
+    if url.is_empty() {
+        log::error!("missing argument: url");
+        return Ok(ExitCode::FAILURE);
     }
 
-    let jenkins = Jenkins::new(&user, &token, &url);
+    let token_file = if args.token_file.is_empty() {
+        expand_home(&context.token_file)
+    } else {
+        args.token_file.clone()
+    };
+    let host = url
+        .parse::<hyper::Uri>()?
+        .host()
+        .unwrap_or_default()
+        .to_string();
 
-    match args.commands {
-        Commands::Shutdown { state } => {
-            jenkins.shutdown(state).await?;
+    #[cfg_attr(not(target_os = "linux"), allow(unused_mut))]
+    let mut providers: Vec<Box<dyn CredentialProvider>> = vec![
+        Box::new(auth::Plain {
+            user: user.clone(),
+            token: token.clone(),
+        }),
+        Box::new(auth::TokenFile {
+            user: user.clone(),
+            path: token_file.into(),
+        }),
+        Box::new(auth::TokenCommand {
+            user: user.clone(),
+            command: context.token_command.clone(),
+        }),
+        Box::new(auth::Netrc { host }),
+    ];
+    #[cfg(target_os = "linux")]
+    providers.push(Box::new(auth::Keyring {
+        user: user.clone(),
+        url: url.clone(),
+    }));
+
+    let mut headers = context.headers.clone();
+    headers.extend(args.headers.iter().cloned());
+    let tls_path = |flag: &str, configured: &str| {
+        let path = if flag.is_empty() {
+            expand_home(configured)
+        } else {
+            flag.to_string()
+        };
+        (!path.is_empty()).then(|| path.into())
+    };
+    // Flag, context, default; zero disables a timeout
+    let timeout = |flag: Option<u64>, configured: &str, default: &str| -> Result<_> {
+        let ms = match flag {
+            Some(ms) => ms,
+            None if configured.is_empty() => human::parse_duration(default)?,
+            None => human::parse_duration(configured)?,
+        };
+        Ok((ms > 0).then(|| std::time::Duration::from_millis(ms)))
+    };
+    let options = ClientOptions {
+        auth_mode: args.auth.or(context.auth).unwrap_or_default(),
+        headers,
+        tls: TlsOptions {
+            ca_cert: tls_path(&args.ca_cert, &context.ca_cert),
+            client_cert: tls_path(&args.client_cert, &context.client_cert),
+            client_key: tls_path(&args.client_key, &context.client_key),
+            insecure_skip_verify: args.insecure_skip_verify || context.insecure_skip_verify,
+        },
+        proxy: ProxyOptions {
+            proxy: [&args.proxy, &context.proxy]
+                .into_iter()
+                .find(|p| !p.is_empty())
+                .cloned(),
+            no_proxy: [&args.no_proxy, &context.no_proxy]
+                .into_iter()
+                .find(|p| !p.is_empty())
+                .cloned(),
+        },
+        connect_timeout: timeout(
+            args.connect_timeout,
+            &context.connect_timeout,
+            CONNECT_TIMEOUT,
+        )?,
+        timeout: timeout(args.timeout, &context.timeout, REQUEST_TIMEOUT)?,
+        retries: args.retries.or(context.retries).unwrap_or(RETRIES),
+        rate_limit: args.rate_limit.or(context.rate_limit),
+        record: (!args.record.is_empty()).then(|| args.record.clone().into()),
+        replay: (!args.replay.is_empty()).then(|| args.replay.clone().into()),
+        verbosity: args.verbose,
+    };
+
+    let mut client = JenkinsClient::new(&url, &auth::Chain(providers), options)?;
+    // One cache per context, per instance and user without a context
+    let cache_context =
+        Some(config.active_name(&args.context)).filter(|name| config.contexts.contains_key(name));
+    let cache_ttl = match args.cache_ttl {
+        Some(ms) => ms,
+        None if context.cache_ttl.is_empty() => 0,
+        None => human::parse_duration(&context.cache_ttl)?,
+    };
+    if cache_ttl > 0 {
+        let ttl = std::time::Duration::from_millis(cache_ttl);
+        if let Some(mut cache) = Cache::new(cache_context.as_deref(), &url, &user, ttl) {
+            if args.refresh {
+                cache = cache.refresh();
+            }
+            client = client.with_cache(cache);
         }
+    }
+    let jenkins = client.jenkins();
+
+    match args.commands {
+        Commands::Shutdown { state } => match state {
+            ShutdownState::On { reason } => {
+                jenkins.quiet_down(&reason).await?;
+            }
+            ShutdownState::Off => {
+                jenkins.cancel_quiet_down().await?;
+            }
+        },
         Commands::Restart { hard } => {
             jenkins.restart(hard).await?;
         }
         Commands::Copy { item, src, dest } => {
-            if let Err(e) = jenkins.copy(item, src, dest).await {
+            let res = match item {
+                CopyItem::Job => jenkins.copy_job(&src, &dest).await,
+                CopyItem::View => jenkins.copy_view(&src, &dest).await,
+            };
+            client.invalidate();
+            if let Err(e) = res {
                 log::error!(
                     "copy {} a directory is not enabled -> {e}",
                     "to".red().bold()
@@ -323,7 +1560,38 @@ pub async fn handle() -> Result<()> {
             }
         }
         Commands::Node { node_commands } => match node_commands {
-            NodeAction::Show { show_commands } => match show_commands {
+            NodeAction::Show {
+                node,
+                thresholds,
+                show_commands: None,
+            } => {
+                if node.is_empty() {
+                    return Err("missing node name".into());
+                }
+                let computer = computer(jenkins, &node).await?;
+
+                println!("{:.<30}{}", "name", computer.display_name);
+                println!(
+                    "{:.<30}{}",
+                    "status",
+                    if computer.offline {
+                        "offline".red()
+                    } else {
+                        "online".green()
+                    }
+                );
+                println!("{:.<30}{}", "labels", computer.labels().join(" "));
+                for (name, value) in computer.report() {
+                    println!("{name:.<30}{value}");
+                }
+                for warning in computer.warnings(&thresholds.into()) {
+                    println!("{}", warning.red());
+                }
+            }
+            NodeAction::Show {
+                show_commands: Some(show_commands),
+                ..
+            } => match show_commands {
                 ShowAction::Raw => {
                     let tree = Tree::new("computer/api/json".to_string());
                     let json_data = jenkins.get_json_data(&tree).await?;
@@ -350,55 +1618,307 @@ pub async fn handle() -> Result<()> {
                     }
                 }
             },
-            NodeAction::List { status } => {
-                let tree = Tree::new("computer/api/json".to_string());
-                let json_data = jenkins.get_json_data(&tree).await?;
+            NodeAction::Health {
+                thresholds,
+                fail_on_warning,
+                selector,
+            } => {
+                let thresholds = thresholds.into();
+                let mut unhealthy = false;
+                for computer in client.nodes().await?.into_iter().filter(|c| {
+                    selector
+                        .as_ref()
+                        .map_or(true, |s| s.matches(&c.display_name, &c.labels()))
+                }) {
+                    let warnings = computer.warnings(&thresholds);
+                    let report = computer
+                        .report()
+                        .into_iter()
+                        .filter(|(name, _)| *name != "architecture")
+                        .map(|(name, value)| format!("{name}: {value}"))
+                        .collect::<Vec<_>>();
 
-                let node_info = Jenkins::system::<node::Info>(json_data.get_ref().as_slice())?;
+                    if warnings.is_empty() {
+                        println!("{:.<40}{}", computer.display_name, "ok".green());
+                    } else {
+                        unhealthy = true;
+                        println!("{:.<40}{}", computer.display_name, "warning".red());
+                    }
+                    println!("    {}", report.join(", "));
+                    for warning in warnings {
+                        println!("    {}", warning.red());
+                    }
+                }
+
+                if fail_on_warning && unhealthy {
+                    return Ok(ExitCode::FAILURE);
+                }
+            }
+            NodeAction::List {
+                status,
+                offline_only,
+                selector,
+            } => {
+                if status || offline_only {
+                    let mut nodes = client.nodes().await?;
+                    if let Some(selector) = selector {
+                        nodes.retain(|c| selector.matches(&c.display_name, &c.labels()));
+                    }
+
+                    let now = human::now_millis();
+                    for node in nodes {
+                        if !node.offline {
+                            if !offline_only {
+                                println!("{:.<40}{}", node.display_name, "online".green());
+                            }
+                            continue;
+                        }
 
-                if status {
-                    for node in node_info.computer {
-                        if node.offline {
-                            println!("{:.<40}{}", node.display_name, "offline".red());
-                        } else {
-                            println!("{:.<40}{}", node.display_name, "online".green());
+                        let mut cause = Vec::new();
+                        if let Some(since) = node.offline_since() {
+                            cause.push(format!(
+                                "for {}",
+                                human::duration(now.saturating_sub(since))
+                            ));
                         }
+                        if let Some(by) = node.offline_by() {
+                            cause.push(format!("by {by}"));
+                        }
+                        if !node.offline_reason().is_empty() {
+                            cause.push(format!("({})", node.offline_reason()));
+                        }
+
+                        println!(
+                            "{:.<40}{:<21}{}",
+                            node.display_name,
+                            node.offline_status().red(),
+                            cause.join(" ")
+                        );
                     }
                 } else {
-                    for node in node_info.computer {
-                        println!("{}", node.display_name);
+                    for node in client.node_labels().await?.into_iter().filter(|n| {
+                        selector
+                            .as_ref()
+                            .map_or(true, |s| s.matches(&n.name, &n.labels()))
+                    }) {
+                        println!("{}", node.name);
                     }
                 }
             }
-            NodeAction::Set { node, state } => {
-                let tree = Tree::new(format!("computer/{node}"));
-                jenkins.set(&tree, state).await?;
+            NodeAction::Set {
+                node,
+                selector,
+                state,
+            } => {
+                let nodes = match &selector {
+                    Some(selector) => select_nodes(&client, selector).await?,
+                    None => vec![node],
+                };
+
+                let res = on_nodes(&nodes, selector.is_some(), |node| {
+                    set_node(jenkins, node, state.clone())
+                })
+                .await;
+                client.invalidate();
+                res?;
             }
-        },
-        Commands::Job { job_commands } => match job_commands {
-            JobAction::List { job } => {
-                if job.is_empty() {
-                    let tree =
-                        Tree::new("api/json?tree=jobs[fullDisplayName,fullName,name]".to_string());
-                    let json_data = jenkins.get_json_data(&tree).await?;
+            NodeAction::Create {
+                node,
+                file,
+                executors,
+                labels,
+                remote_fs,
+                description,
+                launcher,
+                host,
+                port,
+                credentials_id,
+            } => {
+                if let Some(file) = file {
+                    let config = read_input(&file)?;
+                    let Some(node) = xml::element_text(&config, "name") else {
+                        return Err(format!("no <name> element found in {file}").into());
+                    };
+                    let remote_fs =
+                        xml::element_text(&config, "remoteFS").unwrap_or_else(|| "/".to_string());
+
+                    // Jenkins has no endpoint to create a node out of a config.xml,
+                    // so create a bare agent and replace its configuration
+                    let form = serde_json::json!({
+                        "name": node,
+                        "nodeDescription": "",
+                        "numExecutors": "1",
+                        "remoteFS": remote_fs,
+                        "labelString": "",
+                        "mode": "NORMAL",
+                        "launcher": {
+                            "stapler-class": "hudson.slaves.JNLPLauncher",
+                            "$class": "hudson.slaves.JNLPLauncher",
+                        },
+                        "retentionStrategy": {
+                            "stapler-class": "hudson.slaves.RetentionStrategy$Always",
+                            "$class": "hudson.slaves.RetentionStrategy$Always",
+                        },
+                        "nodeProperties": { "stapler-class-bag": "true" },
+                        "type": "hudson.slaves.DumbSlave",
+                    });
+                    jenkins.create_node(&node, &form).await?;
+                    client.invalidate();
+
+                    let tree = node_tree(&node, "config.xml");
+                    jenkins.post_config(&tree, config).await?;
+                    log::info!("created node {node}");
+                } else {
+                    let launcher = match launcher {
+                        Launcher::Jnlp => serde_json::json!({
+                            "stapler-class": "hudson.slaves.JNLPLauncher",
+                            "$class": "hudson.slaves.JNLPLauncher",
+                        }),
+                        Launcher::Ssh => serde_json::json!({
+                            "stapler-class": "hudson.plugins.sshslaves.SSHLauncher",
+                            "$class": "hudson.plugins.sshslaves.SSHLauncher",
+                            "host": host,
+                            "port": port.to_string(),
+                            "credentialsId": credentials_id,
+                            "sshHostKeyVerificationStrategy": {
+                                "stapler-class": "hudson.plugins.sshslaves.verifiers.KnownHostsFileKeyVerificationStrategy",
+                                "$class": "hudson.plugins.sshslaves.verifiers.KnownHostsFileKeyVerificationStrategy",
+                            },
+                        }),
+                    };
+
+                    let form = serde_json::json!({
+                        "name": node,
+                        "nodeDescription": description,
+                        "numExecutors": executors.to_string(),
+                        "remoteFS": remote_fs,
+                        "labelString": labels.replace(',', " "),
+                        "mode": "NORMAL",
+                        "launcher": launcher,
+                        "retentionStrategy": {
+                            "stapler-class": "hudson.slaves.RetentionStrategy$Always",
+                            "$class": "hudson.slaves.RetentionStrategy$Always",
+                        },
+                        "nodeProperties": { "stapler-class-bag": "true" },
+                        "type": "hudson.slaves.DumbSlave",
+                    });
+                    jenkins.create_node(&node, &form).await?;
+                    client.invalidate();
+                    log::info!("created node {node}");
+                }
+            }
+            NodeAction::Delete {
+                node,
+                selector,
+                yes,
+            } => {
+                let nodes = match &selector {
+                    Some(selector) => select_nodes(&client, selector).await?,
+                    None => vec![node],
+                };
 
-                    let job_info = Jenkins::system::<job::Info>(json_data.get_ref().as_slice())?;
+                if !yes
+                    && !nodes.is_empty()
+                    && !confirm(format!("Delete the node(s) {}?", nodes.join(", ")).as_str())?
+                {
+                    log::info!("node removal aborted");
+                    return Ok(ExitCode::SUCCESS);
+                }
+
+                let res = on_nodes(&nodes, selector.is_some(), |node| async move {
+                    let tree = node_tree(&node, "");
+                    let res = jenkins.do_delete(&tree).await?;
+                    jenkins::accepted(res, &format!("delete node {node}"))?;
+                    Ok(format!("node {node} deleted"))
+                })
+                .await;
+                client.invalidate();
+                res?;
+            }
+            NodeAction::Config { config_commands } => match config_commands {
+                ConfigAction::Get { name } => {
+                    let tree = node_tree(&name, "config.xml");
+                    let data = jenkins.get_json_data(&tree).await?;
+                    print!("{}", String::from_utf8(data.into_inner())?);
+                }
+                ConfigAction::Apply { name, file } => {
+                    let tree = node_tree(&name, "config.xml");
+                    let res = jenkins.post_config(&tree, read_input(&file)?).await;
+                    client.invalidate();
+                    res?;
+                }
+            },
+            NodeAction::Labels { labels_commands } => {
+                let (node, label, add) = match labels_commands {
+                    LabelsAction::Add { node, label } => (node, label, true),
+                    LabelsAction::Remove { node, label } => (node, label, false),
+                };
 
-                    for job in job_info.jobs {
-                        let class = job.class.rsplit_once('.').unwrap().1.to_lowercase();
-                        let inner_job = String::new();
+                let tree = node_tree(&node, "config.xml");
+                let data = jenkins.get_json_data(&tree).await?;
+                let config = String::from_utf8(data.into_inner())?;
 
-                        rec_walk(&class, &jenkins, job.full_name.as_str(), inner_job).await?;
+                let current = xml::element_text(&config, "label").unwrap_or_default();
+                let mut labels = current.split_whitespace().collect::<Vec<_>>();
+                if add {
+                    if labels.contains(&label.as_str()) {
+                        log::info!("node {node} already has the label {label}");
+                        return Ok(ExitCode::SUCCESS);
                     }
+                    labels.push(&label);
                 } else {
-                    let tree =
-                        Tree::new("api/json?tree=builds[number,url],nextBuildNumber".to_string())
-                            .build_path(&job);
+                    if !labels.contains(&label.as_str()) {
+                        log::info!("node {node} has no label {label}");
+                        return Ok(ExitCode::SUCCESS);
+                    }
+                    labels.retain(|l| *l != label);
+                }
 
-                    let json_data = jenkins.get_json_data(&tree).await?;
-                    let build_info = Jenkins::system::<BuildInfo>(json_data.get_ref().as_slice())?;
+                let Some(config) =
+                    xml::set_element_text(&config, "slave", "label", &labels.join(" "))
+                else {
+                    return Err(format!("unexpected config.xml of the node {node}").into());
+                };
+                let res = jenkins.post_config(&tree, config).await;
+                client.invalidate();
+                res?;
+            }
+            NodeAction::Drain {
+                node,
+                selector,
+                reason,
+                timeout,
+                disconnect,
+            } => {
+                let nodes = match &selector {
+                    Some(selector) => select_nodes(&client, selector).await?,
+                    None => vec![node],
+                };
 
-                    for build in build_info.builds {
+                let res = on_nodes(&nodes, selector.is_some(), |node| {
+                    drain_node(jenkins, node, reason.clone(), timeout, disconnect)
+                })
+                .await;
+                client.invalidate();
+                res?;
+            }
+        },
+        Commands::Job { job_commands } => match job_commands {
+            JobAction::List { job } => {
+                if job.is_empty() {
+                    for job in client.jobs().await? {
+                        let (folders, name) = job
+                            .full_name
+                            .rsplit_once('/')
+                            .unwrap_or(("", job.full_name.as_str()));
+                        for folder in folders.split('/').filter(|f| !f.is_empty()) {
+                            print!("{} => ", folder.blue().bold());
+                        }
+                        println!("{name}");
+                    }
+                } else {
+                    let builds = suggest_jobs(&client, &job, client.builds(&job).await).await?;
+                    for build in builds {
                         println!("{}", build.number);
                     }
                 }
@@ -408,42 +1928,38 @@ pub async fn handle() -> Result<()> {
                 params,
                 follow,
             } => {
-                let tree =
-                    Tree::new("api/json?tree=builds[number,url],nextBuildNumber".to_string())
-                        .build_path(&job);
-
-                let json_data = jenkins.get_json_data(&tree).await?;
-                let build_info = Jenkins::system::<BuildInfo>(json_data.get_ref().as_slice())?;
-
-                log::info!("started build {}", build_info.next_build_number);
+                let params = match params.as_str() {
+                    "" => None,
+                    "-" => Some(Vec::new()),
+                    _ => Some(
+                        params
+                            .split(',')
+                            .map(|p| {
+                                let (name, value) = p.split_once('=').unwrap_or((p, ""));
+                                (name.to_string(), value.to_string())
+                            })
+                            .collect::<Vec<_>>(),
+                    ),
+                };
 
-                jenkins.build(&job, params).await?;
+                let item =
+                    suggest_jobs(&client, &job, client.trigger(&job, params.as_deref()).await)
+                        .await?;
+                log::info!("queued as item {}", item.id);
 
                 if follow {
-                    let mut offset: usize = 0;
-                    loop {
-                        let tree = Tree::new(format!(
-                            "{}/logText/progressiveText?start={offset}",
-                            build_info.next_build_number
-                        ))
-                        .build_path(&job);
-
-                        match jenkins.get_console_log(&tree).await {
-                            Some((data, current_offset)) => {
-                                if !data.get_ref().is_empty() {
-                                    print!("{}", String::from_utf8_lossy(data.get_ref()));
-                                    offset = current_offset;
-                                }
-                            }
-                            None => {
-                                break;
-                            }
-                        }
-                    }
+                    let build = client.wait_for_build(item.id).await?;
+                    log::info!("started build {build}");
+                    follow_console(&client, &job, build).await?;
                 }
             }
             JobAction::Remove { job } => {
-                jenkins.remove(&job).await?;
+                let res = jenkins.remove(&job).await?;
+                if res.status().is_client_error() || res.status().is_server_error() {
+                    let e = format!("failed to remove {job}: {}", res.status());
+                    return suggest_jobs(&client, &job, Err(e.into())).await;
+                }
+                client.invalidate();
             }
             JobAction::Download { item, job, build } => match item {
                 BuildItem::Artifact => {
@@ -481,7 +1997,8 @@ pub async fn handle() -> Result<()> {
                             let tree = Tree::new(format!("{n}/artifact/*zip*/archive.zip"))
                                 .build_path(&job);
 
-                            match jenkins.get_json_data(&tree).await {
+                            let res = jenkins.get_json_data(&tree).await;
+                            match suggest_jobs(&client, &job, res).await {
                                 Ok(data) => {
                                     log::info!("fetching build {n} artifacts from the {job}");
                                     let job_base = std::path::Path::new(&job)
@@ -504,21 +2021,31 @@ pub async fn handle() -> Result<()> {
                 }
                 BuildItem::Log => {
                     let tree = Tree::new(format!("{build}/consoleText")).build_path(&job);
-                    let data = jenkins.get_json_data(&tree).await?;
+                    let data =
+                        suggest_jobs(&client, &job, jenkins.get_json_data(&tree).await).await?;
                     let log = String::from_utf8(data.into_inner())?;
                     print!("{log}");
                 }
             },
             JobAction::Kill { signal, job, build } => {
-                let tree = Tree::new(build).build_path(&job);
-                if let Err(e) = jenkins.kill(&tree, signal).await {
+                let tree = Tree::new(build.clone()).build_path(&job);
+                let res = match jenkins.kill(&tree, signal).await {
+                    Ok(res) if res.status() == hyper::StatusCode::NOT_FOUND => Err(format!(
+                        "failed to stop build {build} of {job}: {}",
+                        res.status()
+                    )
+                    .into()),
+                    res => res.map(drop),
+                };
+                if let Err(e) = suggest_jobs(&client, &job, res).await {
                     log::error!("{e}");
                 }
             }
             JobAction::Rebuild { job, build } => {
                 let tree = Tree::new(format!("{build}/api/json?tree=actions")).build_path(&job);
 
-                let json_data = jenkins.get_json_data(&tree).await?;
+                let json_data =
+                    suggest_jobs(&client, &job, jenkins.get_json_data(&tree).await).await?;
                 let action_obj = Jenkins::system::<job::ActionObj>(json_data.get_ref().as_slice())?;
 
                 let actions_classes = action_obj.actions.as_array().unwrap();
@@ -540,21 +2067,541 @@ pub async fn handle() -> Result<()> {
 
                 log::info!("rebuilding the build {build} with params:");
 
-                let mut params = String::new();
+                let mut params = Vec::new();
                 for params_action in build_params.actions {
                     for parameters in params_action.parameters {
-                        params.push_str(
-                            format!("&{}={}", parameters.name, parameters.value).as_str(),
-                        );
                         log::info!("{:-<40}{}", parameters.name, parameters.value);
+                        let value = match parameters.value {
+                            serde_json::Value::String(value) => value,
+                            value => value.to_string(),
+                        };
+                        params.push((parameters.name, value));
+                    }
+                }
+
+                let item = client.trigger(&job, Some(&params)).await?;
+                log::info!("queued as item {}", item.id);
+            }
+        },
+        Commands::Builds { builds_commands } => match builds_commands {
+            BuildsAction::Running {
+                node,
+                longer_than,
+                kill,
+                signal,
+            } => {
+                let executable = "currentExecutable[number,url,timestamp,estimatedDuration]";
+                let tree = Tree::new(format!(
+                    "computer/api/json?tree=computer[displayName,executors[number,{executable}],oneOffExecutors[number,{executable}]]"
+                ));
+                let json_data = jenkins.get_json_data(&tree).await?;
+                let load = Jenkins::system::<node::Load>(json_data.get_ref().as_slice())?;
+
+                let now = human::now_millis();
+                let (mut killed, mut failed) = (0, 0);
+                for computer in load.computer {
+                    if !node.is_empty() && computer.display_name != node {
+                        continue;
+                    }
+
+                    for executor in computer.executors.iter().chain(&computer.one_off_executors) {
+                        let Some(node::Executable {
+                            number: Some(number),
+                            url: Some(url),
+                            timestamp: Some(timestamp),
+                            estimated_duration,
+                        }) = &executor.current_executable
+                        else {
+                            continue;
+                        };
+
+                        let elapsed = now.saturating_sub(*timestamp);
+                        if longer_than.map_or(false, |limit| elapsed < limit) {
+                            continue;
+                        }
+
+                        let remaining = match estimated_duration {
+                            Some(estimated) if *estimated > 0 => {
+                                let estimated = estimated.unsigned_abs();
+                                if estimated > elapsed {
+                                    human::duration(estimated - elapsed)
+                                } else {
+                                    "overdue".to_string()
+                                }
+                            }
+                            _ => "unknown".to_string(),
+                        };
+
+                        let job = job::path_from_url(url);
+                        println!(
+                            "{:<40}{:<8}{:<20}{:<5}{:<10}{}",
+                            job,
+                            format!("#{number}"),
+                            computer.display_name,
+                            executor.number,
+                            human::duration(elapsed),
+                            remaining
+                        );
+
+                        if kill {
+                            log::info!("interrupting build {number} of the {job}");
+                            let tree = Tree::new(number.to_string()).build_path(&job);
+                            killed += 1;
+                            let res = jenkins.kill(&tree, signal.clone()).await.and_then(|res| {
+                                jenkins::accepted(
+                                    res,
+                                    &format!("interrupt build {number} of {job}"),
+                                )
+                            });
+                            if let Err(e) = res {
+                                failed += 1;
+                                log::error!("{e}");
+                            }
+                        }
+                    }
+                }
+
+                if failed > 0 {
+                    return Err(format!("{failed} of {killed} interruptions failed").into());
+                }
+            }
+        },
+        Commands::Queue { queue_commands } => {
+            let tree = Tree::new(
+                "queue/api/json?tree=items[id,why,inQueueSince,stuck,blocked,buildable,task[name,url]]"
+                    .to_string(),
+            );
+            let json_data = jenkins.get_json_data(&tree).await?;
+            let queue_info = Jenkins::system::<queue::Info>(json_data.get_ref().as_slice())?;
+
+            match queue_commands {
+                QueueAction::List => {
+                    let now = human::now_millis();
+                    for item in queue_info.items {
+                        println!(
+                            "{:<8}{:<40}{:<10}{:<10}{}",
+                            item.id,
+                            item.path(),
+                            human::duration(now.saturating_sub(item.in_queue_since)),
+                            item.state(),
+                            item.why.as_deref().unwrap_or_default()
+                        );
+                    }
+                }
+                QueueAction::Cancel { id, job, all_stuck } => {
+                    let ids = queue_info
+                        .items
+                        .iter()
+                        .filter(|item| match (&id, &job) {
+                            (Some(id), _) => item.id == *id,
+                            (_, Some(job)) => item.path() == job.trim_matches('/'),
+                            _ => all_stuck && item.stuck,
+                        })
+                        .map(|item| item.id)
+                        .collect::<Vec<_>>();
+
+                    if ids.is_empty() {
+                        log::warn!("no matching items in the queue");
+                    }
+
+                    let mut failed = 0;
+                    for id in &ids {
+                        log::info!("cancelling queue item {id}");
+                        let res = jenkins.cancel(*id).await.and_then(|res| {
+                            jenkins::accepted(res, &format!("cancel queue item {id}"))
+                        });
+                        if let Err(e) = res {
+                            failed += 1;
+                            log::error!("{e}");
+                        }
+                    }
+
+                    if failed > 0 {
+                        return Err(
+                            format!("{failed} of {} cancellations failed", ids.len()).into()
+                        );
+                    }
+                }
+            }
+        }
+        Commands::Plugin { plugin_commands } => match plugin_commands {
+            PluginAction::List { updates } => {
+                let tree = Tree::new("pluginManager/api/json?depth=1".to_string());
+                let json_data = jenkins.get_json_data(&tree).await?;
+                let mut plugin_info =
+                    Jenkins::system::<plugin::Info>(json_data.get_ref().as_slice())?;
+                plugin_info
+                    .plugins
+                    .sort_by(|a, b| a.short_name.cmp(&b.short_name));
+
+                for plugin in plugin_info.plugins {
+                    if updates && !plugin.has_update {
+                        continue;
+                    }
+
+                    let mut state = vec![if plugin.enabled {
+                        "enabled"
+                    } else {
+                        "disabled"
+                    }];
+                    if plugin.enabled && !plugin.active {
+                        state.push("inactive");
+                    }
+                    if plugin.pinned {
+                        state.push("pinned");
+                    }
+                    let update = if plugin.has_update {
+                        "update available".yellow()
+                    } else {
+                        "".normal()
+                    };
+                    let dependencies = plugin
+                        .dependencies
+                        .iter()
+                        .map(|d| {
+                            if d.optional {
+                                format!("{}:{} (optional)", d.short_name, d.version)
+                            } else {
+                                format!("{}:{}", d.short_name, d.version)
+                            }
+                        })
+                        .collect::<Vec<_>>();
+
+                    println!(
+                        "{:<40}{:<30}{:<20}{:<18}{}",
+                        plugin.short_name,
+                        plugin.version,
+                        state.join(","),
+                        update,
+                        dependencies.join(", ")
+                    );
+                }
+            }
+            PluginAction::Install { plugins } => {
+                let res = jenkins.install_plugins(&plugins).await?;
+                jenkins::accepted(res, &format!("install {}", plugins.join(", ")))?;
+                log::info!("installation of {} scheduled", plugins.join(", "));
+            }
+            PluginAction::Uninstall { name, yes } => {
+                if !yes && !confirm(format!("Uninstall the plugin {name}?").as_str())? {
+                    log::info!("plugin removal aborted");
+                    return Ok(ExitCode::SUCCESS);
+                }
+                let res = jenkins.uninstall_plugin(&name).await?;
+                jenkins::accepted(res, &format!("uninstall the plugin {name}"))?;
+            }
+            PluginAction::Enable { name } => {
+                let res = jenkins.enable_plugin(&name, true).await?;
+                jenkins::accepted(res, &format!("enable the plugin {name}"))?;
+            }
+            PluginAction::Disable { name } => {
+                let res = jenkins.enable_plugin(&name, false).await?;
+                jenkins::accepted(res, &format!("disable the plugin {name}"))?;
+            }
+            PluginAction::Export => {
+                let tree = Tree::new("pluginManager/api/json?depth=1".to_string());
+                let json_data = jenkins.get_json_data(&tree).await?;
+                let mut plugin_info =
+                    Jenkins::system::<plugin::Info>(json_data.get_ref().as_slice())?;
+                plugin_info
+                    .plugins
+                    .sort_by(|a, b| a.short_name.cmp(&b.short_name));
+
+                for plugin in plugin_info.plugins {
+                    println!("{}:{}", plugin.short_name, plugin.version);
+                }
+            }
+        },
+        Commands::Script { script_commands } => match script_commands {
+            ScriptAction::Run {
+                file,
+                expr,
+                node,
+                yes,
+            } => {
+                let script = match expr {
+                    Some(expr) => expr,
+                    None if file == "-" && !yes => {
+                        return Err("reading the script from stdin requires --yes".into());
+                    }
+                    None => read_input(&file)?,
+                };
+
+                let (tree, target) = if node.is_empty() {
+                    (
+                        Tree::new("scriptText".to_string()),
+                        "the controller".to_string(),
+                    )
+                } else {
+                    (node_tree(&node, "scriptText"), format!("the node {node}"))
+                };
+
+                if !yes && !confirm(format!("Run the script on {target}?").as_str())? {
+                    log::info!("script execution aborted");
+                    return Ok(ExitCode::SUCCESS);
+                }
+
+                let res = jenkins.script(&tree, &script).await?;
+                if !res.status().is_success() {
+                    return Err(format!("script console returned {}", res.status()).into());
+                }
+
+                Jenkins::stream(res, &mut std::io::stdout()).await?;
+            }
+        },
+        Commands::Credentials {
+            credentials_commands,
+        } => match credentials_commands {
+            CredentialsAction::List { store } => {
+                let tree = store.tree("api/json?tree=credentials[id,typeName,description]");
+                let json_data = jenkins.get_json_data(&tree).await?;
+                let info = Jenkins::system::<credentials::Info>(json_data.get_ref().as_slice())?;
+
+                // the scope is not exported by the json api, it is only
+                // available in the (secret-free) config.xml of each credential
+                let store = &store;
+                let scopes = futures::stream::iter(&info.credentials)
+                    .map(|c| async move {
+                        let tree = store.credential(&c.id, "config.xml");
+                        let data = jenkins.get_json_data(&tree).await.ok()?;
+                        xml::element_text(String::from_utf8_lossy(data.get_ref()).as_ref(), "scope")
+                    })
+                    .buffered(CREDENTIAL_FETCHES)
+                    .collect::<Vec<_>>()
+                    .await;
+
+                for (credential, scope) in info.credentials.iter().zip(scopes) {
+                    println!(
+                        "{:<40}{:<40}{:<10}{}",
+                        credential.id,
+                        credential.type_name,
+                        scope.unwrap_or_else(|| "-".to_string()),
+                        credential.description.as_deref().unwrap_or_default()
+                    );
+                }
+            }
+            CredentialsAction::Create { spec } => {
+                let tree = spec.store.tree("createCredentials");
+                jenkins.post_config(&tree, spec.config_xml()?).await?;
+                log::info!("created credentials {}", spec.id);
+            }
+            CredentialsAction::Update { spec } => {
+                let tree = spec.store.credential(&spec.id, "config.xml");
+                jenkins.post_config(&tree, spec.config_xml()?).await?;
+                log::info!("updated credentials {}", spec.id);
+            }
+            CredentialsAction::Delete { id, store, yes } => {
+                if !yes && !confirm(format!("Delete the credentials {id}?").as_str())? {
+                    log::info!("credentials removal aborted");
+                    return Ok(ExitCode::SUCCESS);
+                }
+
+                let tree = store.credential(&id, "");
+                let res = jenkins.do_delete(&tree).await?;
+                jenkins::accepted(res, &format!("delete credentials {id}"))?;
+                log::info!("deleted credentials {id}");
+            }
+        },
+        Commands::View { view_commands } => match view_commands {
+            ViewAction::List { owner } => {
+                let tree = owner.tree("api/json?tree=views[name,url]".to_string());
+                let json_data = jenkins.get_json_data(&tree).await?;
+                let view_info = Jenkins::system::<view::Info>(json_data.get_ref().as_slice())?;
+
+                for view in view_info.views {
+                    let class = view
+                        .class
+                        .rsplit_once('.')
+                        .map_or(view.class.as_str(), |c| c.1);
+                    println!("{:<40}{}", view.name, class);
+                }
+            }
+            ViewAction::Show { view, owner } => {
+                let tree = owner.view(&view, "api/json?tree=jobs[name,color]");
+                let json_data = jenkins.get_json_data(&tree).await?;
+                let jobs = Jenkins::system::<view::Jobs>(json_data.get_ref().as_slice())?;
+
+                for job in jobs.jobs {
+                    let status = job.color.as_deref().map_or("-", job::status);
+                    let status = match status {
+                        "success" => status.green(),
+                        "failed" => status.red(),
+                        "unstable" => status.yellow(),
+                        _ => status.normal(),
+                    };
+                    println!("{:.<40}{}", job.name, status);
+                }
+            }
+            ViewAction::Create {
+                name,
+                kind,
+                regex,
+                recurse,
+                owner,
+            } => {
+                let config = match kind {
+                    ViewType::List => view::list_view_xml(&name, &regex, recurse),
+                    ViewType::My => view::my_view_xml(&name),
+                };
+                let tree = owner.tree(format!("createView?name={}", encode(&name)));
+                jenkins.post_config(&tree, config).await?;
+                log::info!("created view {name}");
+            }
+            ViewAction::AddJob { view, job, owner } => {
+                let tree = owner.view(&view, "addJobToView").param("name", &job);
+                let res = jenkins.post(&tree).await?;
+                jenkins::accepted(res, &format!("add {job} to the view {view}"))?;
+            }
+            ViewAction::RemoveJob { view, job, owner } => {
+                let tree = owner.view(&view, "removeJobFromView").param("name", &job);
+                let res = jenkins.post(&tree).await?;
+                jenkins::accepted(res, &format!("remove {job} from the view {view}"))?;
+            }
+            ViewAction::Config {
+                config_commands,
+                owner,
+            } => match config_commands {
+                ConfigAction::Get { name } => {
+                    let tree = owner.view(&name, "config.xml");
+                    let data = jenkins.get_json_data(&tree).await?;
+                    print!("{}", String::from_utf8(data.into_inner())?);
+                }
+                ConfigAction::Apply { name, file } => {
+                    let tree = owner.view(&name, "config.xml");
+                    jenkins.post_config(&tree, read_input(&file)?).await?;
+                }
+            },
+            ViewAction::Delete { view, owner, yes } => {
+                if !yes && !confirm(format!("Delete the view {view}?").as_str())? {
+                    log::info!("view removal aborted");
+                    return Ok(ExitCode::SUCCESS);
+                }
+
+                let res = jenkins.do_delete(&owner.view(&view, "")).await?;
+                jenkins::accepted(res, &format!("delete the view {view}"))?;
+                log::info!("deleted view {view}");
+            }
+        },
+        Commands::Token { token_commands } => match token_commands {
+            TokenAction::Create { name, save } => {
+                let res = jenkins.generate_token(&name).await?;
+                if !res.status().is_success() {
+                    return Err(format!("token generation failed: {}", res.status()).into());
+                }
+
+                let mut body = Vec::new();
+                Jenkins::stream(res, &mut body).await?;
+                let new_token = Jenkins::system::<user::NewToken>(body.as_slice())?;
+
+                println!("{:.<20}{}", "name", new_token.data.token_name);
+                println!("{:.<20}{}", "uuid", new_token.data.token_uuid);
+                println!("{:.<20}{}", "token", new_token.data.token_value);
+
+                if save {
+                    let mut config = Config::load()?;
+                    let name = config.active_name(&args.context);
+                    let context = config.contexts.entry(name.clone()).or_default();
+                    if context.url.is_empty() {
+                        context.url.clone_from(&url);
+                    }
+                    if context.user.is_empty() {
+                        context.user.clone_from(&user);
+                    }
+                    context.token = new_token.data.token_value;
+                    if config.current_context.is_empty() {
+                        config.current_context.clone_from(&name);
+                    }
+
+                    let path = config.save()?;
+                    log::info!("token saved to the context {name} in {}", path.display());
+                }
+            }
+            TokenAction::List => {
+                let tree = Tree::new("scriptText".to_string());
+                let forbidden = "token list runs a script in the script console, which requires \
+                                 the Overall/Administer permission (403)";
+                let res = match jenkins.script(&tree, user::TOKEN_LIST_SCRIPT).await {
+                    Err(e) if e.is::<jenkins::PermissionDenied>() => return Err(forbidden.into()),
+                    Ok(res) if res.status() == hyper::StatusCode::FORBIDDEN => {
+                        return Err(forbidden.into())
                     }
+                    res => res?,
+                };
+                if !res.status().is_success() {
+                    return Err(format!("script console returned {}", res.status()).into());
                 }
 
-                jenkins.rebuild(&job, params).await?;
+                let mut body = Vec::new();
+                Jenkins::stream(res, &mut body).await?;
+
+                let now = human::now_millis();
+                let ago = |ts: &str| match ts.parse::<u64>() {
+                    Ok(ts) if ts > 0 => format!("{} ago", human::duration(now.saturating_sub(ts))),
+                    _ => "never".to_string(),
+                };
+                for line in String::from_utf8_lossy(&body).lines() {
+                    let fields = line.split('\t').collect::<Vec<_>>();
+                    let [uuid, name, created, use_count, last_use] = fields[..] else {
+                        continue;
+                    };
+                    println!(
+                        "{:<38}{:<30}{:<16}{:<8}{}",
+                        uuid,
+                        name,
+                        ago(created),
+                        use_count,
+                        ago(last_use)
+                    );
+                }
+            }
+            TokenAction::Revoke { uuid, yes } => {
+                if !yes && !confirm(format!("Revoke the token {uuid}?").as_str())? {
+                    log::info!("token revocation aborted");
+                    return Ok(ExitCode::SUCCESS);
+                }
+                let res = jenkins.revoke_token(&uuid).await?;
+                jenkins::accepted(res, &format!("revoke the token {uuid}"))?;
             }
         },
+        Commands::Whoami => {
+            let tree = Tree::new("me/api/json?tree=id,fullName".to_string());
+            let me = match jenkins.get_json_data(&tree).await {
+                Ok(json_data) => Jenkins::system::<user::Me>(json_data.get_ref().as_slice())?,
+                Err(e) => {
+                    println!("{:.<20}{}", "token", "invalid".red());
+                    return Err(e);
+                }
+            };
+
+            let tree = Tree::new("whoAmI/api/json".to_string());
+            let json_data = jenkins.get_json_data(&tree).await?;
+            let who = Jenkins::system::<user::WhoAmI>(json_data.get_ref().as_slice())?;
+
+            println!("{:.<20}{}", "user", me.id);
+            println!("{:.<20}{}", "full name", me.full_name);
+            println!(
+                "{:.<20}{}",
+                "token",
+                if who.authenticated && !who.anonymous {
+                    "valid".green()
+                } else {
+                    "anonymous".yellow()
+                }
+            );
+            println!("{:.<20}{}", "authorities", who.authorities.join(", "));
+        }
         Commands::Info => println!("{url}"),
+        Commands::Completion { .. } => unreachable!("handled before connecting"),
+        Commands::Complete { index, words } => {
+            let Some(target) = completion::target(Args::command(), &words, index) else {
+                return Ok(ExitCode::FAILURE);
+            };
+            let cache = Cache::new(cache_context.as_deref(), &url, &user, completion::CACHE_TTL);
+            for name in completion::complete(jenkins, cache.as_ref(), &target).await? {
+                println!("{name}");
+            }
+        }
     }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
@@ -0,0 +1,430 @@
+//! How requests are authenticated and where the secrets come from.
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::Result;
+
+#[derive(Clone, Copy, Default, PartialEq, clap::ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthMode {
+    /// `Authorization: Basic` with the user and API token.
+    #[default]
+    Basic,
+    /// `Authorization: Bearer` with the token, for controllers behind an
+    /// OIDC proxy.
+    Bearer,
+    /// No credentials, for read-only anonymous access.
+    Anonymous,
+}
+
+pub enum Auth {
+    Basic { user: String, token: String },
+    Bearer(String),
+    Anonymous,
+}
+
+impl Auth {
+    /// Resolve the credentials needed by the mode.
+    pub fn new(mode: AuthMode, provider: &dyn CredentialProvider) -> Result<Self> {
+        if mode == AuthMode::Anonymous {
+            return Ok(Self::Anonymous);
+        }
+
+        let Some(credentials) = provider.credentials()? else {
+            return Err(format!("no credentials found in the {}", provider.name()).into());
+        };
+
+        match mode {
+            AuthMode::Basic if credentials.user.is_empty() => {
+                Err("basic authentication requires a user".into())
+            }
+            AuthMode::Basic => Ok(Self::Basic {
+                user: credentials.user,
+                token: credentials.token,
+            }),
+            AuthMode::Bearer => Ok(Self::Bearer(credentials.token)),
+            AuthMode::Anonymous => Ok(Self::Anonymous),
+        }
+    }
+
+    /// Value of the `Authorization` header, if any.
+    pub fn header(&self) -> Option<String> {
+        match self {
+            Self::Basic { user, token } => Some(format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(format!("{user}:{token}"))
+            )),
+            Self::Bearer(token) => Some(format!("Bearer {token}")),
+            Self::Anonymous => None,
+        }
+    }
+}
+
+pub struct Credentials {
+    pub user: String,
+    pub token: String,
+}
+
+pub trait CredentialProvider {
+    /// Short description used in error messages.
+    fn name(&self) -> &'static str;
+
+    /// `Ok(None)` means the source has nothing for us and the next one may be
+    /// tried, errors are reported as is.
+    fn credentials(&self) -> Result<Option<Credentials>>;
+}
+
+/// User and token given through the environment, flags or the config file.
+pub struct Plain {
+    pub user: String,
+    pub token: String,
+}
+
+impl CredentialProvider for Plain {
+    fn name(&self) -> &'static str {
+        "token"
+    }
+
+    fn credentials(&self) -> Result<Option<Credentials>> {
+        if self.token.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Credentials {
+            user: self.user.clone(),
+            token: self.token.clone(),
+        }))
+    }
+}
+
+/// Token stored in a file which must not be accessible by group or others.
+pub struct TokenFile {
+    pub user: String,
+    pub path: PathBuf,
+}
+
+impl CredentialProvider for TokenFile {
+    fn name(&self) -> &'static str {
+        "token file"
+    }
+
+    fn credentials(&self) -> Result<Option<Credentials>> {
+        if self.path.as_os_str().is_empty() {
+            return Ok(None);
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = std::fs::metadata(&self.path)?.permissions().mode();
+            if mode & 0o077 != 0 {
+                return Err(format!(
+                    "token file {} is accessible by group or others (mode {:o}), expected 600",
+                    self.path.display(),
+                    mode & 0o777
+                )
+                .into());
+            }
+        }
+
+        let token = std::fs::read_to_string(&self.path)?.trim().to_string();
+
+        Ok(Some(Credentials {
+            user: self.user.clone(),
+            token,
+        }))
+    }
+}
+
+/// Credential helper whose stdout is the token, e.g. `pass show jenkins` or
+/// `op read op://ci/jenkins/token`.
+pub struct TokenCommand {
+    pub user: String,
+    pub command: String,
+}
+
+impl CredentialProvider for TokenCommand {
+    fn name(&self) -> &'static str {
+        "token command"
+    }
+
+    fn credentials(&self) -> Result<Option<Credentials>> {
+        if self.command.is_empty() {
+            return Ok(None);
+        }
+
+        let output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .stderr(std::process::Stdio::inherit())
+            .output()?;
+        if !output.status.success() {
+            return Err(format!("token command failed with {}", output.status).into());
+        }
+        let token = String::from_utf8(output.stdout)?.trim().to_string();
+        if token.is_empty() {
+            return Err("token command printed no token".into());
+        }
+
+        Ok(Some(Credentials {
+            user: self.user.clone(),
+            token,
+        }))
+    }
+}
+
+/// `machine` entry of `~/.netrc` (or the file `NETRC` points to).
+pub struct Netrc {
+    pub host: String,
+}
+
+impl Netrc {
+    fn path() -> Option<PathBuf> {
+        match std::env::var("NETRC") {
+            Ok(path) => Some(PathBuf::from(path)),
+            Err(_) => Some(PathBuf::from(std::env::var("HOME").ok()?).join(".netrc")),
+        }
+    }
+
+    fn lookup(data: &str, host: &str) -> Option<Credentials> {
+        let mut tokens = data.split_whitespace();
+        let (mut matched, mut default) = (None, None);
+        let mut current: Option<(bool, String, String)> = None;
+
+        let mut finish = |entry: Option<(bool, String, String)>| {
+            if let Some((is_default, user, token)) = entry {
+                let credentials = Some(Credentials { user, token });
+                if is_default {
+                    default = default.take().or(credentials);
+                } else if matched.is_none() {
+                    matched = credentials;
+                }
+            }
+        };
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "machine" => {
+                    finish(current.take());
+                    if tokens.next() == Some(host) {
+                        current = Some((false, String::new(), String::new()));
+                    }
+                }
+                "default" => {
+                    finish(current.take());
+                    current = Some((true, String::new(), String::new()));
+                }
+                "login" => {
+                    let login = tokens.next().unwrap_or_default();
+                    if let Some(entry) = current.as_mut() {
+                        entry.1 = login.to_string();
+                    }
+                }
+                "password" => {
+                    let password = tokens.next().unwrap_or_default();
+                    if let Some(entry) = current.as_mut() {
+                        entry.2 = password.to_string();
+                    }
+                }
+                _ => {}
+            }
+        }
+        finish(current.take());
+
+        matched.or(default)
+    }
+}
+
+impl CredentialProvider for Netrc {
+    fn name(&self) -> &'static str {
+        "netrc"
+    }
+
+    fn credentials(&self) -> Result<Option<Credentials>> {
+        let Some(path) = Self::path() else {
+            return Ok(None);
+        };
+
+        match std::fs::read_to_string(path) {
+            Ok(data) => Ok(Self::lookup(&data, &self.host)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Secret Service keyring entry stored with
+/// `secret-tool store --label=jenkinsctl service jenkinsctl url <URL> user <USER>`.
+#[cfg(target_os = "linux")]
+pub struct Keyring {
+    pub user: String,
+    pub url: String,
+}
+
+#[cfg(target_os = "linux")]
+impl CredentialProvider for Keyring {
+    fn name(&self) -> &'static str {
+        "keyring"
+    }
+
+    fn credentials(&self) -> Result<Option<Credentials>> {
+        let mut command = std::process::Command::new("secret-tool");
+        command.args(["lookup", "service", "jenkinsctl", "url", &self.url]);
+        if !self.user.is_empty() {
+            command.args(["user", &self.user]);
+        }
+
+        let output = match command.stderr(std::process::Stdio::null()).output() {
+            Ok(output) => output,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let token = String::from_utf8(output.stdout)?.trim().to_string();
+        if !output.status.success() || token.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Credentials {
+            user: self.user.clone(),
+            token,
+        }))
+    }
+}
+
+/// Try the providers in order, the first one with credentials wins.
+pub struct Chain(pub Vec<Box<dyn CredentialProvider>>);
+
+impl CredentialProvider for Chain {
+    fn name(&self) -> &'static str {
+        "credential chain"
+    }
+
+    fn credentials(&self) -> Result<Option<Credentials>> {
+        for provider in &self.0 {
+            if let Some(credentials) = provider.credentials()? {
+                log::debug!("using credentials from the {}", provider.name());
+                return Ok(Some(credentials));
+            }
+        }
+
+        let tried = self.0.iter().map(|p| p.name()).collect::<Vec<_>>();
+        Err(format!("no credentials found (tried: {})", tried.join(", ")).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(provider: &dyn CredentialProvider) -> Option<String> {
+        provider.credentials().unwrap().map(|c| c.token)
+    }
+
+    /// A file in a scratch directory of the test.
+    fn scratch(name: &str, data: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("jenkinsctl-auth-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn netrc_lookup() {
+        let data = "machine other.example.com login bob password nope\n\
+                    default login anonymous password guest\n\
+                    machine ci.example.com\n  login admin\n  password s3cret\n\
+                    machine ci.example.com login second password ignored\n";
+
+        let credentials = Netrc::lookup(data, "ci.example.com").unwrap();
+        assert_eq!(credentials.user, "admin");
+        assert_eq!(credentials.token, "s3cret");
+
+        let credentials = Netrc::lookup(data, "unknown.example.com").unwrap();
+        assert_eq!(credentials.user, "anonymous");
+        assert_eq!(credentials.token, "guest");
+
+        let data = "machine ci.example.com login admin password s3cret";
+        assert!(Netrc::lookup(data, "other.example.com").is_none());
+        assert_eq!(
+            Netrc::lookup(data, "ci.example.com").unwrap().token,
+            "s3cret"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn token_file_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = scratch("token", "s3cret\n");
+        let provider = TokenFile {
+            user: "admin".to_string(),
+            path: path.clone(),
+        };
+
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let e = provider.credentials().err().unwrap();
+        assert!(e.to_string().contains("(mode 644), expected 600"), "{e}");
+
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        assert_eq!(token(&provider).as_deref(), Some("s3cret"));
+
+        let provider = TokenFile {
+            user: "admin".to_string(),
+            path: PathBuf::new(),
+        };
+        assert!(token(&provider).is_none());
+    }
+
+    #[test]
+    fn token_command() {
+        let command = |command: &str| TokenCommand {
+            user: "admin".to_string(),
+            command: command.to_string(),
+        };
+
+        assert_eq!(token(&command("echo s3cret")).as_deref(), Some("s3cret"));
+        assert!(token(&command("")).is_none());
+
+        let e = command("exit 3").credentials().err().unwrap();
+        assert!(e.to_string().starts_with("token command failed"), "{e}");
+        let e = command("printf '\\n'").credentials().err().unwrap();
+        assert_eq!(e.to_string(), "token command printed no token");
+    }
+
+    #[test]
+    fn first_source_with_a_token_wins() {
+        let plain = |token: &str| -> Box<dyn CredentialProvider> {
+            Box::new(Plain {
+                user: "admin".to_string(),
+                token: token.to_string(),
+            })
+        };
+        let command = |command: &str| -> Box<dyn CredentialProvider> {
+            Box::new(TokenCommand {
+                user: "admin".to_string(),
+                command: command.to_string(),
+            })
+        };
+
+        let chain = Chain(vec![plain(""), command("echo first"), plain("second")]);
+        assert_eq!(token(&chain).as_deref(), Some("first"));
+
+        // Errors are not skipped
+        let chain = Chain(vec![command("exit 1"), plain("second")]);
+        assert!(chain.credentials().is_err());
+
+        let e = Chain(vec![plain(""), command("")])
+            .credentials()
+            .err()
+            .unwrap();
+        assert_eq!(
+            e.to_string(),
+            "no credentials found (tried: token, token command)"
+        );
+    }
+}
@@ -0,0 +1,102 @@
+//! On-disk cache of controller metadata (job names, node names and labels),
+//! one directory per context (per instance and user without a context) under
+//! `$XDG_CACHE_HOME/jenkinsctl` (default: `~/.cache/jenkinsctl`). Entries are
+//! json files, stale once older than the cache TTL or when refreshing
+//! (`--refresh`).
+use serde::{de::DeserializeOwned, Serialize};
+use std::{path::PathBuf, time::Duration};
+use urlencoding::encode;
+
+use crate::Result;
+
+pub struct Cache {
+    dir: PathBuf,
+    ttl: Duration,
+    /// Entries are written but never read.
+    refresh: bool,
+}
+
+impl Cache {
+    /// Cache of the `context` in use, or of the instance at `url` as seen by
+    /// `user` without one. `None` without a cache directory.
+    pub fn new(context: Option<&str>, url: &str, user: &str, ttl: Duration) -> Option<Self> {
+        let base = match std::env::var("XDG_CACHE_HOME") {
+            Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(std::env::var("HOME").ok()?).join(".cache"),
+        };
+        let slug = |name: &str| {
+            name.chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || "@.-".contains(c) {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect::<String>()
+        };
+
+        let dir = if let Some(context) = context {
+            base.join("jenkinsctl").join("context").join(slug(context))
+        } else {
+            let instance = url
+                .split_once("://")
+                .map_or(url, |(_, rest)| rest)
+                .trim_end_matches('/');
+            base.join("jenkinsctl")
+                .join(slug(&format!("{user}@{instance}")))
+        };
+
+        Some(Self {
+            dir,
+            ttl,
+            refresh: false,
+        })
+    }
+
+    /// Fetch everything again and update the entries.
+    #[must_use]
+    pub fn refresh(mut self) -> Self {
+        self.refresh = true;
+        self
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", encode(key)))
+    }
+
+    /// The entry saved under `key` unless it is stale or unreadable.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        if self.refresh {
+            return None;
+        }
+
+        let path = self.path(key);
+        let age = std::fs::metadata(&path).ok()?.modified().ok()?.elapsed();
+        if age.map_or(true, |age| age > self.ttl) {
+            return None;
+        }
+
+        serde_json::from_slice(&std::fs::read(&path).ok()?).ok()
+    }
+
+    pub fn put<T: Serialize>(&self, key: &str, value: &T) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("failed to create {}: {e}", self.dir.display()))?;
+
+        // Written aside then renamed, so a concurrent reader never sees a
+        // partial entry
+        let path = self.path(key);
+        let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+        std::fs::write(&tmp, serde_json::to_vec(value)?)
+            .map_err(|e| format!("failed to write {}: {e}", tmp.display()))?;
+        std::fs::rename(&tmp, &path)?;
+
+        Ok(())
+    }
+
+    /// Drop an entry, e.g. once the controller changed it.
+    pub fn remove(&self, key: &str) {
+        let _ = std::fs::remove_file(self.path(key));
+    }
+}
@@ -0,0 +1,285 @@
+//! Typed async API over the Jenkins endpoints used most, the structs come
+//! from the `job`, `node` and `queue` modules. Anything else is reachable
+//! through the underlying `Jenkins` client. The job tree and the node list
+//! are served from a `Cache` when one is set.
+use async_recursion::async_recursion;
+use bytes::Bytes;
+use futures::Stream;
+use http_body_util::BodyExt;
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+
+use crate::{
+    auth::CredentialProvider,
+    cache::Cache,
+    jenkins::{ClientOptions, Jenkins},
+    job, node, queue,
+    url::Tree,
+    Result,
+};
+
+/// Interval between polls of a queue item or of a running build log.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+const JOBS: &str = "jobs";
+const NODES: &str = "nodes";
+
+pub struct JenkinsClient {
+    jenkins: Jenkins,
+    cache: Option<Cache>,
+}
+
+impl JenkinsClient {
+    pub fn new(
+        url: &str,
+        provider: &dyn CredentialProvider,
+        options: ClientOptions,
+    ) -> Result<Self> {
+        Ok(Self {
+            jenkins: Jenkins::new(url, provider, options)?,
+            cache: None,
+        })
+    }
+
+    /// Serve the job tree and the node names and labels from `cache` while
+    /// fresh.
+    #[must_use]
+    pub fn with_cache(mut self, cache: Cache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Forget the cached job tree and node labels, once either changed.
+    pub fn invalidate(&self) {
+        if let Some(cache) = &self.cache {
+            cache.remove(JOBS);
+            cache.remove(NODES);
+        }
+    }
+
+    fn cached<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.cache.as_ref()?.get(key)
+    }
+
+    fn store<T: serde::Serialize>(&self, key: &str, value: &T) {
+        if let Some(cache) = &self.cache {
+            if let Err(e) = cache.put(key, value) {
+                log::debug!("{e}");
+            }
+        }
+    }
+
+    /// The underlying client, for endpoints without a typed method.
+    pub fn jenkins(&self) -> &Jenkins {
+        &self.jenkins
+    }
+
+    async fn get<T: DeserializeOwned>(&self, tree: &Tree) -> Result<T> {
+        let json_data = self.jenkins.get_json_data(tree).await?;
+
+        Ok(serde_json::from_slice(json_data.get_ref())?)
+    }
+
+    /// Every job of the instance, folders are walked recursively and
+    /// `full_name` is the job path.
+    pub async fn jobs(&self) -> Result<Vec<job::Jobs>> {
+        if let Some(jobs) = self.cached(JOBS) {
+            return Ok(jobs);
+        }
+
+        let mut jobs = Vec::new();
+        rec_walk(self, "", &mut jobs).await?;
+        self.store(JOBS, &jobs);
+
+        Ok(jobs)
+    }
+
+    /// Job paths close to `job` when it does not exist, `None` when it does
+    /// (or when the controller could not tell). Candidates come from the
+    /// cached job tree, or from the parent folder of `job` without one.
+    pub async fn similar_jobs(&self, job: &str) -> Result<Option<Vec<String>>> {
+        let tree = Tree::new("api/json?tree=name".to_string()).build_path(job);
+        if self.jenkins.get(&tree).await?.status() != hyper::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        // The whole tree only when it is cached, a typo must not walk every
+        // folder. A cached tree may still list the job if it was deleted
+        // since, the parent folder is listed then.
+        let job = job.trim_matches('/');
+        let jobs = match self.cached::<Vec<job::Jobs>>(JOBS) {
+            Some(jobs) if !jobs.iter().any(|j| j.full_name == job) => jobs,
+            cached => {
+                if cached.is_some() {
+                    self.invalidate();
+                }
+                let parent = job.rsplit_once('/').map_or("", |(parent, _)| parent);
+                let tree =
+                    Tree::new("api/json?tree=jobs[fullDisplayName,fullName,name]".to_string())
+                        .build_path(parent);
+                self.get::<job::Info>(&tree)
+                    .await
+                    .map_or_else(|_| Vec::new(), |info| info.jobs)
+            }
+        };
+
+        Ok(Some(job::similar(
+            job,
+            jobs.iter()
+                .map(|j| j.full_name.as_str())
+                .filter(|name| *name != job),
+        )))
+    }
+
+    /// Builds of a job, newest first.
+    pub async fn builds(&self, job: &str) -> Result<Vec<job::Build>> {
+        let tree = Tree::new("api/json?tree=builds[number,url],nextBuildNumber".to_string())
+            .build_path(job);
+
+        Ok(self.get::<job::BuildInfo>(&tree).await?.builds)
+    }
+
+    /// Schedule a build (see `Jenkins::build` for `params`) and return its
+    /// queue item.
+    pub async fn trigger(
+        &self,
+        job: &str,
+        params: Option<&[(String, String)]>,
+    ) -> Result<queue::Item> {
+        let res = self.jenkins.build(job, params).await?;
+        if !res.status().is_success() {
+            return Err(format!("failed to build {job}: {}", res.status()).into());
+        }
+
+        // The queue item is only known from the redirect: .../queue/item/<ID>/
+        let location = res
+            .headers()
+            .get(hyper::header::LOCATION)
+            .and_then(|l| l.to_str().ok())
+            .ok_or_else(|| format!("no queue item returned for the build of {job}"))?;
+        let id = location
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .and_then(|id| id.parse::<u64>().ok())
+            .ok_or_else(|| format!("unexpected queue item location: {location}"))?;
+
+        self.queue_item(id).await
+    }
+
+    pub async fn queue_item(&self, id: u64) -> Result<queue::Item> {
+        self.get(&Tree::new(format!("queue/item/{id}/api/json")))
+            .await
+    }
+
+    /// Wait until a queue item leaves the queue, returns the number of the
+    /// started build.
+    pub async fn wait_for_build(&self, id: u64) -> Result<u32> {
+        loop {
+            let item = self.queue_item(id).await?;
+            if let Some(executable) = item.executable {
+                return Ok(executable.number);
+            }
+            if item.cancelled {
+                return Err(format!("queue item {id} was cancelled").into());
+            }
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Every node with its current state, never cached.
+    pub async fn nodes(&self) -> Result<Vec<node::Computer>> {
+        let tree = Tree::new("computer/api/json".to_string());
+
+        Ok(self.get::<node::Info>(&tree).await?.computer)
+    }
+
+    /// Names and labels of every node, served from the cache while fresh.
+    pub async fn node_labels(&self) -> Result<Vec<node::Labels>> {
+        if let Some(nodes) = self.cached(NODES) {
+            return Ok(nodes);
+        }
+
+        let nodes = self
+            .nodes()
+            .await?
+            .iter()
+            .map(node::Labels::from)
+            .collect::<Vec<_>>();
+        self.store(NODES, &nodes);
+
+        Ok(nodes)
+    }
+
+    /// Console output of a build as it is written, the stream ends once the
+    /// build is finished.
+    pub fn console(&self, job: &str, build: u32) -> impl Stream<Item = Result<Bytes>> + '_ {
+        let job = job.to_string();
+
+        futures::stream::try_unfold(Some(0), move |offset| {
+            let job = job.clone();
+            async move {
+                let Some(mut offset) = offset else {
+                    return Ok(None);
+                };
+
+                loop {
+                    let tree = Tree::new(format!("{build}/logText/progressiveText"))
+                        .build_path(&job)
+                        .param("start", &offset.to_string());
+                    let res = self.jenkins.get(&tree).await?;
+                    if !res.status().is_success() {
+                        return Err(format!(
+                            "failed to get the console of {job} #{build}: {}",
+                            res.status()
+                        )
+                        .into());
+                    }
+
+                    // X-Text-Size is the offset to continue from, X-More-Data
+                    // is only present while the build is running
+                    let size = res
+                        .headers()
+                        .get("x-text-size")
+                        .and_then(|s| s.to_str().ok()?.parse::<usize>().ok());
+                    let more = res.headers().contains_key("x-more-data");
+                    let chunk = res.collect().await?.to_bytes();
+                    offset = size.unwrap_or(offset + chunk.len());
+
+                    if !more {
+                        return Ok(Some((chunk, None)));
+                    }
+                    if !chunk.is_empty() {
+                        return Ok(Some((chunk, Some(offset))));
+                    }
+
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+            }
+        })
+    }
+}
+
+#[async_recursion]
+async fn rec_walk(client: &JenkinsClient, folder: &str, jobs: &mut Vec<job::Jobs>) -> Result<()> {
+    let tree = Tree::new("api/json?tree=jobs[fullDisplayName,fullName,name]".to_string())
+        .build_path(folder);
+    let info = client.get::<job::Info>(&tree).await?;
+
+    for job in info.jobs {
+        let class = job
+            .class
+            .rsplit_once('.')
+            .map_or(job.class.as_str(), |c| c.1)
+            .to_lowercase();
+
+        if class == "folder" {
+            rec_walk(client, &job.full_name, jobs).await?;
+        } else {
+            jobs.push(job);
+        }
+    }
+
+    Ok(())
+}
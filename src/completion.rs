@@ -0,0 +1,274 @@
+//! Shell completion: the `clap_complete` scripts, plus a wrapper completing
+//! job, node and view names with the hidden `complete-names <INDEX> <WORDS>...`
+//! command. It exits with status 1 unless the word at `INDEX` is such a
+//! name, so the shell falls back to the generated completion.
+use clap::{Command, ValueEnum};
+use std::time::Duration;
+
+use jenkinsctl::{cache::Cache, jenkins::Jenkins, url::Tree, Result};
+
+/// How long the names of a folder are served from the cache.
+pub const CACHE_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+const BASH: &str = r#"
+_jenkinsctl_names() {
+    local out
+    if out=$("${COMP_WORDS[0]}" complete-names "$COMP_CWORD" "${COMP_WORDS[@]}" 2>/dev/null); then
+        COMPREPLY=()
+        [[ -n $out ]] && mapfile -t COMPREPLY <<< "$out"
+        if [[ ${#COMPREPLY[@]} -eq 1 && ${COMPREPLY[0]} == */ ]]; then
+            compopt -o nospace
+        fi
+        return 0
+    fi
+    _jenkinsctl "$@"
+}
+
+complete -F _jenkinsctl_names -o bashdefault -o default jenkinsctl
+"#;
+
+const ZSH: &str = r#"
+_jenkinsctl_names() {
+    local out
+    if out=$("${words[1]}" complete-names $((CURRENT - 1)) "${words[@]}" 2>/dev/null); then
+        [[ -n $out ]] || return 1
+        local -a names
+        names=("${(@f)out}")
+        compadd -S '' -- ${(M)names:#*/}
+        compadd -- ${names:#*/}
+        return 0
+    fi
+    _jenkinsctl "$@"
+}
+
+compdef _jenkinsctl_names jenkinsctl
+"#;
+
+const FISH: &str = r"
+function __jenkinsctl_names
+    set -l words (commandline -opc) (commandline -ct)
+    $words[1] complete-names (math (count $words) - 1) $words 2>/dev/null
+end
+
+complete -c jenkinsctl -f -n '__jenkinsctl_names >/dev/null' -a '(__jenkinsctl_names)'
+";
+
+/// Print the completion script of a shell.
+pub fn print(shell: Shell, mut cmd: Command) {
+    let (generator, names) = match shell {
+        Shell::Bash => (clap_complete::Shell::Bash, BASH),
+        Shell::Zsh => (clap_complete::Shell::Zsh, ZSH),
+        Shell::Fish => (clap_complete::Shell::Fish, FISH),
+    };
+    let name = cmd.get_name().to_string();
+
+    clap_complete::generate(generator, &mut cmd, name, &mut std::io::stdout());
+    print!("{names}");
+}
+
+#[derive(Debug, PartialEq)]
+enum Kind {
+    Job,
+    Folder,
+    Node,
+    View,
+}
+
+impl Kind {
+    fn of(id: &str) -> Option<Self> {
+        match id {
+            "job" => Some(Self::Job),
+            "folder" => Some(Self::Folder),
+            "node" => Some(Self::Node),
+            "view" => Some(Self::View),
+            _ => None,
+        }
+    }
+}
+
+/// A name being completed.
+pub struct Target {
+    kind: Kind,
+    /// Folder the names are relative to (`--folder`).
+    folder: String,
+    word: String,
+}
+
+/// The name being completed at `index` of the command line `words`, if the
+/// word there is an option value or a positional argument naming a job, a
+/// node or a view.
+pub fn target(mut cmd: Command, words: &[String], index: usize) -> Option<Target> {
+    cmd.build();
+    let word = words.get(index).cloned().unwrap_or_default();
+    if word.starts_with('-') {
+        return None;
+    }
+
+    let mut cmd = &cmd;
+    let mut positionals = 0;
+    let mut folder = String::new();
+    let mut value_of = None;
+    for word in words.iter().take(index).skip(1) {
+        if let Some(arg) = value_of.take() {
+            if arg == "folder" {
+                folder.clone_from(word);
+            }
+            continue;
+        }
+
+        let arg = if let Some(long) = word.strip_prefix("--") {
+            if let Some(("folder", value)) = long.split_once('=') {
+                folder = value.to_string();
+            }
+            if long.contains('=') {
+                continue;
+            }
+            cmd.get_arguments().find(|a| a.get_long() == Some(long))
+        } else if let Some(shorts) = word.strip_prefix('-').filter(|s| !s.is_empty()) {
+            // Only the last of grouped flags may take the next word
+            let short = shorts.chars().last()?;
+            if shorts.chars().count() > 1 {
+                continue;
+            }
+            cmd.get_arguments().find(|a| a.get_short() == Some(short))
+        } else {
+            match cmd.find_subcommand(word) {
+                Some(sub) => {
+                    cmd = sub;
+                    positionals = 0;
+                }
+                None => positionals += 1,
+            }
+            continue;
+        };
+
+        if let Some(arg) = arg.filter(|a| a.get_action().takes_values()) {
+            value_of = Some(arg.get_id().as_str());
+        }
+    }
+
+    let kind = if let Some(id) = value_of {
+        Kind::of(id)?
+    } else {
+        let mut args = cmd.get_positionals().collect::<Vec<_>>();
+        args.sort_by_key(|a| a.get_index());
+        Kind::of(args.get(positionals)?.get_id().as_str())?
+    };
+    if kind == Kind::Folder {
+        folder.clear();
+    }
+
+    Some(Target { kind, folder, word })
+}
+
+#[derive(serde::Deserialize)]
+struct Named {
+    #[serde(rename = "_class", default)]
+    class: String,
+    #[serde(alias = "displayName")]
+    name: String,
+}
+
+#[derive(serde::Deserialize)]
+struct Listing {
+    #[serde(default, alias = "computer", alias = "views")]
+    jobs: Vec<Named>,
+}
+
+/// Jobs holding other jobs: folders, organization folders and multibranch
+/// projects.
+fn is_folder(class: &str) -> bool {
+    class.ends_with("Folder") || class.contains("MultiBranch")
+}
+
+/// Names of a listing, folders end with a slash.
+async fn names(
+    jenkins: &Jenkins,
+    cache: Option<&Cache>,
+    path: &str,
+    folder: &str,
+) -> Result<Vec<String>> {
+    let key = format!("complete/{path}/{folder}");
+    if let Some(names) = cache.and_then(|c| c.get(&key)) {
+        return Ok(names);
+    }
+
+    let json_data = jenkins
+        .get_json_data(&Tree::new(path.to_string()).build_path(folder))
+        .await?;
+    let listing = serde_json::from_slice::<Listing>(json_data.get_ref())?;
+    let names = listing
+        .jobs
+        .into_iter()
+        .map(|n| {
+            if is_folder(&n.class) {
+                format!("{}/", n.name)
+            } else {
+                n.name
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(cache) = cache {
+        if let Err(e) = cache.put(&key, &names) {
+            log::debug!("{e}");
+        }
+    }
+
+    Ok(names)
+}
+
+/// Candidates for the target, matching the word being completed.
+pub async fn complete(
+    jenkins: &Jenkins,
+    cache: Option<&Cache>,
+    target: &Target,
+) -> Result<Vec<String>> {
+    let (parent, prefix) = match target.kind {
+        Kind::Job | Kind::Folder => target
+            .word
+            .rsplit_once('/')
+            .map_or(("", target.word.as_str()), |(parent, prefix)| {
+                (parent, prefix)
+            }),
+        Kind::Node | Kind::View => ("", target.word.as_str()),
+    };
+    let folder = [target.folder.as_str(), parent]
+        .iter()
+        .filter(|p| !p.is_empty())
+        .copied()
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let path = match target.kind {
+        Kind::Job | Kind::Folder => "api/json?tree=jobs[name]",
+        Kind::Node => "computer/api/json?tree=computer[displayName]",
+        Kind::View => "api/json?tree=views[name]",
+    };
+    let folder = if target.kind == Kind::Node {
+        ""
+    } else {
+        &folder
+    };
+
+    Ok(names(jenkins, cache, path, folder)
+        .await?
+        .into_iter()
+        .filter(|name| name.starts_with(prefix))
+        .filter(|name| target.kind != Kind::Folder || name.ends_with('/'))
+        .map(|name| {
+            if parent.is_empty() {
+                name
+            } else {
+                format!("{parent}/{name}")
+            }
+        })
+        .collect())
+}
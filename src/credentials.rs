@@ -0,0 +1,79 @@
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+
+use crate::xml;
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct Info {
+    pub credentials: Vec<Credential>,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Credential {
+    pub id: String,
+    pub type_name: String,
+    pub description: Option<String>,
+}
+
+pub enum Secret {
+    UsernamePassword {
+        username: String,
+        password: String,
+    },
+    Text(String),
+    SshKey {
+        username: String,
+        private_key: String,
+    },
+    File {
+        file_name: String,
+        data: Vec<u8>,
+    },
+}
+
+/// Credential config.xml as accepted by the credentials plugin
+/// `createCredentials` and `credential/<ID>/config.xml` endpoints.
+pub fn config_xml(id: &str, scope: &str, description: &str, secret: &Secret) -> String {
+    let common = format!(
+        "<scope>{}</scope><id>{}</id><description>{}</description>",
+        xml::escape(scope),
+        xml::escape(id),
+        xml::escape(description)
+    );
+
+    match secret {
+        Secret::UsernamePassword { username, password } => format!(
+            "<com.cloudbees.plugins.credentials.impl.UsernamePasswordCredentialsImpl>{common}\
+             <username>{}</username><password>{}</password>\
+             </com.cloudbees.plugins.credentials.impl.UsernamePasswordCredentialsImpl>",
+            xml::escape(username),
+            xml::escape(password)
+        ),
+        Secret::Text(text) => format!(
+            "<org.jenkinsci.plugins.plaincredentials.impl.StringCredentialsImpl>{common}\
+             <secret>{}</secret>\
+             </org.jenkinsci.plugins.plaincredentials.impl.StringCredentialsImpl>",
+            xml::escape(text)
+        ),
+        Secret::SshKey {
+            username,
+            private_key,
+        } => format!(
+            "<com.cloudbees.jenkins.plugins.sshcredentials.impl.BasicSSHUserPrivateKey>{common}\
+             <username>{}</username>\
+             <privateKeySource class=\"com.cloudbees.jenkins.plugins.sshcredentials.impl.BasicSSHUserPrivateKey$DirectEntryPrivateKeySource\">\
+             <privateKey>{}</privateKey></privateKeySource>\
+             </com.cloudbees.jenkins.plugins.sshcredentials.impl.BasicSSHUserPrivateKey>",
+            xml::escape(username),
+            xml::escape(private_key)
+        ),
+        Secret::File { file_name, data } => format!(
+            "<org.jenkinsci.plugins.plaincredentials.impl.FileCredentialsImpl>{common}\
+             <fileName>{}</fileName><secretBytes>{}</secretBytes>\
+             </org.jenkinsci.plugins.plaincredentials.impl.FileCredentialsImpl>",
+            xml::escape(file_name),
+            base64::engine::general_purpose::STANDARD.encode(data)
+        ),
+    }
+}
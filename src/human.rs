@@ -0,0 +1,104 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Milliseconds since the unix epoch, the unit Jenkins uses for timestamps.
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX))
+        .unwrap_or_default()
+}
+
+/// Format a duration given in milliseconds as e.g. `1d 2h`, `3h 4m` or `5m 6s`.
+pub fn duration(ms: u64) -> String {
+    let secs = ms / 1000;
+    let (days, hours, mins, secs) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+
+    if days > 0 {
+        format!("{days}d {hours}h")
+    } else if hours > 0 {
+        format!("{hours}h {mins}m")
+    } else if mins > 0 {
+        format!("{mins}m {secs}s")
+    } else {
+        format!("{secs}s")
+    }
+}
+
+/// Parse a duration such as `90s`, `15m`, `2h` or `1h30m` into milliseconds.
+pub fn parse_duration(s: &str) -> Result<u64, String> {
+    let mut total = 0;
+    let mut num = String::new();
+
+    for c in s.trim().chars() {
+        if c.is_ascii_digit() {
+            num.push(c);
+            continue;
+        }
+
+        let unit = match c {
+            's' => 1000,
+            'm' => 60 * 1000,
+            'h' => 60 * 60 * 1000,
+            'd' => 24 * 60 * 60 * 1000,
+            _ => return Err(format!("invalid duration unit '{c}' in '{s}'")),
+        };
+        let n = num
+            .parse::<u64>()
+            .map_err(|_| format!("invalid duration: '{s}'"))?;
+        total = n
+            .checked_mul(unit)
+            .and_then(|ms| ms.checked_add(total))
+            .ok_or_else(|| format!("duration too large: '{s}'"))?;
+        num.clear();
+    }
+
+    if !num.is_empty() {
+        return Err(format!("missing duration unit in '{s}' (use s, m, h or d)"));
+    }
+
+    Ok(total)
+}
+
+/// Format a byte count using binary units, e.g. `10.3 GiB`.
+#[allow(clippy::cast_precision_loss)]
+pub fn bytes(n: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut size = n as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{n} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+/// Parse a size such as `512M`, `10G` or `10GiB` (binary units) into bytes.
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (num, unit) = s.split_at(split);
+
+    let num = num
+        .parse::<u64>()
+        .map_err(|_| format!("invalid size: '{s}'"))?;
+    let shift = match unit.trim_end_matches("iB").trim_end_matches('B') {
+        "" => 0,
+        "K" | "k" => 10,
+        "M" => 20,
+        "G" => 30,
+        "T" => 40,
+        _ => return Err(format!("invalid size unit in '{s}' (use K, M, G or T)")),
+    };
+
+    if num.leading_zeros() < shift {
+        return Err(format!("size too large: '{s}'"));
+    }
+
+    Ok(num << shift)
+}
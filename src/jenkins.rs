@@ -1,52 +1,253 @@
 #![warn(clippy::all, clippy::pedantic)]
-#![allow(clippy::similar_names)]
-use base64::{self, Engine as _};
+#![allow(
+    clippy::similar_names,
+    clippy::missing_errors_doc,
+    clippy::must_use_candidate
+)]
 use bytes::Bytes;
-use http_body_util::{BodyExt, Empty};
-use hyper::{body::Incoming, Method, Request, Response, StatusCode};
-use hyper_tls::HttpsConnector;
+use http_body_util::{combinators::BoxBody, BodyExt, Full};
+use hyper::{
+    body::Frame,
+    header::{HeaderName, HeaderValue},
+    Method, Request, Response, StatusCode,
+};
 use hyper_util::client::legacy::Client;
 use hyper_util::rt::TokioExecutor;
 use serde::Deserialize;
-use std::str::FromStr;
-use tokio::io::AsyncWriteExt as _;
+use std::{
+    future::Future,
+    hash::{BuildHasher, Hasher},
+    io::Write,
+    path::PathBuf,
+    pin::Pin,
+    str::FromStr,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{io::AsyncWriteExt as _, time::Instant};
 use urlencoding::encode;
 
 use crate::{
-    args::{CopyItem, NodeState, ShutdownState},
-    Result,
+    auth::{Auth, AuthMode, CredentialProvider},
+    proxy::{Proxies, ProxyConnector, ProxyOptions},
+    recording::{Player, Recorder, Recording},
+    tls::{self, TlsOptions},
+    trace::Tracer,
+    url::{Tree, Url},
+    xml, Result,
 };
 
-pub struct Tree {
-    query: String,
+/// Gateway errors a GET is retried on, usually a controller (re)starting
+/// behind a reverse proxy.
+const RETRY_STATUSES: [StatusCode; 3] = [
+    StatusCode::BAD_GATEWAY,
+    StatusCode::SERVICE_UNAVAILABLE,
+    StatusCode::GATEWAY_TIMEOUT,
+];
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Body of the responses, read from the network or from a recording.
+pub type Body = BoxBody<Bytes, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Response body failing once no data arrives for `timeout`, so that a
+/// controller stalling in the middle of a response does not hang a command.
+struct ReadTimeout<B> {
+    body: B,
+    path: String,
+    timeout: Duration,
+    deadline: Pin<Box<tokio::time::Sleep>>,
 }
 
-impl Tree {
-    pub fn new(query: String) -> Self {
-        Self { query }
+impl<B> ReadTimeout<B> {
+    fn new(body: B, path: String, timeout: Duration) -> Self {
+        Self {
+            body,
+            path,
+            timeout,
+            deadline: Box::pin(tokio::time::sleep(timeout)),
+        }
     }
+}
 
-    pub fn build_path(self, path: &str) -> Self {
-        let mut query = self.query;
-        let mut job_path = std::path::Path::new(&path)
-            .iter()
-            .map(|e| e.to_str().unwrap())
-            .collect::<Vec<_>>();
-        job_path.reverse();
+impl<B> hyper::body::Body for ReadTimeout<B>
+where
+    B: hyper::body::Body<Data = Bytes> + Unpin,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    type Data = Bytes;
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<std::result::Result<Frame<Bytes>, Self::Error>>> {
+        let this = &mut *self;
+        if let Poll::Ready(frame) = Pin::new(&mut this.body).poll_frame(cx) {
+            this.deadline.as_mut().reset(Instant::now() + this.timeout);
+            return Poll::Ready(frame.map(|frame| frame.map_err(Into::into)));
+        }
 
-        for component in job_path {
-            query.insert_str(0, format!("job/{component}/").as_str());
+        match this.deadline.as_mut().poll(cx) {
+            Poll::Ready(()) => Poll::Ready(Some(Err(format!(
+                "no data received for {} within {}s (see --timeout)",
+                this.path,
+                this.timeout.as_secs_f64()
+            )
+            .into()))),
+            Poll::Pending => Poll::Pending,
         }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> hyper::body::SizeHint {
+        self.body.size_hint()
+    }
+}
 
-        Self { query }
+/// A mutating request refused with 403, other than for a missing crumb.
+pub struct PermissionDenied {
+    pub path: String,
+}
+
+// Errors are printed with `Debug` when `main` returns, as the message like
+// the string errors
+impl std::fmt::Debug for PermissionDenied {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self.to_string())
     }
 }
 
-impl std::fmt::Display for Tree {
+impl std::fmt::Display for PermissionDenied {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let t = self;
-        write!(f, "{t}")
+        write!(
+            f,
+            "permission denied (403) for {}: the user is probably missing the {} permission",
+            self.path,
+            required_permission(&self.path)
+        )
+    }
+}
+
+impl std::error::Error for PermissionDenied {}
+
+/// Best guess of the permission a mutating endpoint requires.
+fn required_permission(path: &str) -> &'static str {
+    let has = |s: &str| path.contains(s);
+
+    if has("/credentials/") {
+        if has("/createCredentials") {
+            "Credentials/Create"
+        } else if path.ends_with("/doDelete") {
+            "Credentials/Delete"
+        } else {
+            "Credentials/Update"
+        }
+    } else if has("/view/") || has("/createView") {
+        if has("/createView") {
+            "View/Create"
+        } else if path.ends_with("/doDelete") {
+            "View/Delete"
+        } else {
+            "View/Configure"
+        }
+    } else if has("/computer/") {
+        if has("/doCreateItem") {
+            "Computer/Create"
+        } else if path.ends_with("/doDelete") {
+            "Computer/Delete"
+        } else if has("/launchSlaveAgent") {
+            "Computer/Connect"
+        } else if has("/doDisconnect") || has("/toggleOffline") {
+            "Computer/Disconnect"
+        } else if has("/scriptText") {
+            "Overall/Administer"
+        } else {
+            "Computer/Configure"
+        }
+    } else if has("/queue/cancelItem")
+        || path.ends_with("/stop")
+        || path.ends_with("/term")
+        || path.ends_with("/kill")
+    {
+        "Job/Cancel"
+    } else if path.ends_with("/build") || path.ends_with("/buildWithParameters") {
+        "Job/Build"
+    } else if has("/createItem") {
+        "Job/Create"
+    } else if has("/job/") {
+        "Job/Delete or Job/Configure"
+    } else {
+        "Overall/Administer"
+    }
+}
+
+/// Whether the connection was dropped by the server or on the way.
+fn is_connection_reset(mut error: &(dyn std::error::Error + 'static)) -> bool {
+    loop {
+        if let Some(e) = error.downcast_ref::<std::io::Error>() {
+            if matches!(
+                e.kind(),
+                std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::BrokenPipe
+            ) {
+                return true;
+            }
+        }
+        if let Some(e) = error.downcast_ref::<hyper::Error>() {
+            if e.is_incomplete_message() {
+                return true;
+            }
+        }
+
+        match error.source() {
+            Some(source) => error = source,
+            None => return false,
+        }
+    }
+}
+
+/// Delay requested by the server with `Retry-After: <seconds>`.
+fn retry_after(res: &Response<Body>) -> Option<Duration> {
+    let secs = res
+        .headers()
+        .get(hyper::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .parse::<u64>()
+        .ok()?;
+
+    Some(Duration::from_secs(secs).min(RETRY_MAX_DELAY))
+}
+
+/// Exponential backoff with jitter: a random delay between half and the
+/// whole of `base * 2^attempt`, so parallel clients do not retry in sync.
+fn backoff(attempt: u32) -> Duration {
+    let delay = RETRY_BASE_DELAY
+        .saturating_mul(2_u32.saturating_pow(attempt))
+        .min(RETRY_MAX_DELAY);
+    let half = u64::try_from(delay.as_millis() / 2).unwrap_or(u64::MAX);
+    let random = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+
+    Duration::from_millis(half + random % (half + 1))
+}
+
+/// The response of a change, an error naming `what` failed unless the
+/// controller accepted it (2xx, or a 3xx redirect to the changed page).
+pub fn accepted(res: Response<Body>, what: &str) -> Result<Response<Body>> {
+    let status = res.status();
+    if status.is_success() || status.is_redirection() {
+        return Ok(res);
     }
+
+    Err(format!("failed to {what}: {status}").into())
 }
 
 enum Signal {
@@ -68,65 +269,350 @@ impl FromStr for Signal {
     }
 }
 
-pub struct Jenkins<'x> {
-    user: &'x str,
-    pswd: &'x str,
-    url: hyper::Uri,
+/// Connection settings other than the url and credentials.
+#[derive(Default)]
+pub struct ClientOptions {
+    pub auth_mode: AuthMode,
+    /// Extra headers sent with every request (format: `Name: value`).
+    pub headers: Vec<String>,
+    pub tls: TlsOptions,
+    pub proxy: ProxyOptions,
+    /// Time allowed to establish a connection.
+    pub connect_timeout: Option<Duration>,
+    /// Time allowed to wait for the response headers, then for each part of
+    /// the body.
+    pub timeout: Option<Duration>,
+    /// How many times a GET failing with 502/503/504 or a reset connection
+    /// is retried.
+    pub retries: u32,
+    /// Maximum number of requests per second.
+    pub rate_limit: Option<f64>,
+    /// Directory to save the exchanges with the controller to.
+    pub record: Option<PathBuf>,
+    /// Directory of a recording to serve the responses from instead of the
+    /// network.
+    pub replay: Option<PathBuf>,
+    /// HTTP tracing level, see the `trace` module.
+    pub verbosity: u8,
 }
 
-impl<'x> Jenkins<'x> {
-    pub fn new(user: &'x str, pswd: &'x str, jenkins_url: &'x str) -> Self {
-        let url = jenkins_url.parse::<hyper::Uri>().unwrap();
+/// CSRF protection token, bound to the web session in the cookie.
+struct Crumb {
+    field: HeaderName,
+    value: HeaderValue,
+    cookie: Option<HeaderValue>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CrumbInfo {
+    crumb_request_field: String,
+    crumb: String,
+}
 
-        Self { user, pswd, url }
+pub struct Jenkins {
+    auth: Auth,
+    headers: Vec<(HeaderName, HeaderValue)>,
+    crumb: tokio::sync::Mutex<Option<Crumb>>,
+    client: Client<tls::Connector, Full<Bytes>>,
+    proxies: Arc<Proxies>,
+    timeout: Option<Duration>,
+    retries: u32,
+    /// Interval between requests and the earliest time of the next one.
+    throttle: Option<(Duration, tokio::sync::Mutex<Instant>)>,
+    recording: Option<Recording>,
+    tracer: Tracer,
+    url: Url,
+}
+
+impl Jenkins {
+    pub fn new(
+        jenkins_url: &str,
+        provider: &dyn CredentialProvider,
+        options: ClientOptions,
+    ) -> Result<Self> {
+        let url = Url::parse(jenkins_url)?;
+        // Credentials are not needed to replay a recording
+        let auth = if options.replay.is_some() {
+            Auth::Anonymous
+        } else {
+            Auth::new(options.auth_mode, provider)?
+        };
+
+        let mut headers = Vec::new();
+        for header in options.headers {
+            let Some((name, value)) = header.split_once(':') else {
+                return Err(format!("invalid header '{header}' (format: Name: value)").into());
+            };
+            headers.push((
+                HeaderName::from_str(name.trim())?,
+                HeaderValue::from_str(value.trim())?,
+            ));
+        }
+
+        let proxies = Arc::new(Proxies::new(&options.proxy)?);
+        let connector = tls::connector(
+            &options.tls,
+            ProxyConnector::new(proxies.clone(), options.connect_timeout),
+        )?;
+        let client = Client::builder(TokioExecutor::new()).build(connector);
+
+        let throttle = match options.rate_limit {
+            Some(rps) if rps > 0.0 && rps.is_finite() => Some((
+                Duration::from_secs_f64(1.0 / rps),
+                tokio::sync::Mutex::new(Instant::now()),
+            )),
+            Some(rps) => return Err(format!("invalid rate limit: {rps}").into()),
+            None => None,
+        };
+
+        let recording = match (options.record, options.replay) {
+            (Some(_), Some(_)) => return Err("a session cannot be recorded and replayed".into()),
+            (Some(dir), None) => Some(Recording::Record(Recorder::new(&dir)?)),
+            (None, Some(dir)) => Some(Recording::Replay(Player::load(&dir)?)),
+            (None, None) => None,
+        };
+
+        Ok(Self {
+            auth,
+            headers,
+            crumb: tokio::sync::Mutex::new(None),
+            client,
+            proxies,
+            timeout: options.timeout,
+            retries: options.retries,
+            throttle,
+            recording,
+            tracer: Tracer::new(options.verbosity),
+            url,
+        })
+    }
+
+    async fn send_request(&self, url: &hyper::Uri, method: Method) -> Result<Response<Body>> {
+        self.send_request_with_body(url, method, None).await
     }
 
-    async fn send_request(
+    async fn send_request_with_body(
+        &self,
         url: &hyper::Uri,
-        user: &str,
-        pswd: &str,
         method: Method,
-    ) -> Result<Response<Incoming>> {
-        let host = url.host().expect("uri has no host");
-        let port = url.port_u16().unwrap_or(443);
-
-        let scheme = url.scheme_str().unwrap();
-
-        let req = Request::builder()
-            .uri(url)
-            .method(method)
-            .header(hyper::header::HOST, format!("{host}:{port}"))
-            .header(
-                hyper::header::AUTHORIZATION,
-                format!(
-                    "Basic {}",
-                    base64::engine::general_purpose::URL_SAFE.encode(format!("{user}:{pswd}"))
-                ),
-            )
-            .body(Empty::<Bytes>::new())?;
+        content: Option<(&str, Bytes)>,
+    ) -> Result<Response<Body>> {
+        let mutating = method != Method::GET;
+
+        let req = self.request(url, method.clone(), content.clone()).await?;
+        let mut res = self.execute(req).await?;
+
+        // Requests authenticated with an API token are exempt from CSRF
+        // protection, others (bearer, anonymous, password) need a crumb
+        if mutating && res.status() == StatusCode::FORBIDDEN && self.crumb.lock().await.is_none() {
+            let body = res.collect().await?.to_bytes();
+            if !String::from_utf8_lossy(&body).contains("crumb") {
+                return Err(Self::permission_error(url));
+            }
 
-        let res = if scheme == "http" {
-            let stream = tokio::net::TcpStream::connect(format!("{host}:{port}")).await?;
-            let io = hyper_util::rt::TokioIo::new(stream);
+            self.fetch_crumb().await?;
+            let req = self.request(url, method, content).await?;
+            res = self.execute(req).await?;
+        }
+
+        if res.status() == StatusCode::UNAUTHORIZED {
+            return Err("authentication failed (401): check the user and token".into());
+        }
 
-            let (mut sender, conn) = hyper::client::conn::http1::handshake(io).await?;
-            conn.await?;
-            sender.send_request(req).await?
+        if mutating && res.status() == StatusCode::FORBIDDEN {
+            return Err(Self::permission_error(url));
+        }
+
+        Ok(res)
+    }
+
+    fn permission_error(url: &hyper::Uri) -> Box<dyn std::error::Error + Send + Sync> {
+        Box::new(PermissionDenied {
+            path: url.path().to_string(),
+        })
+    }
+
+    async fn request(
+        &self,
+        url: &hyper::Uri,
+        method: Method,
+        content: Option<(&str, Bytes)>,
+    ) -> Result<Request<Full<Bytes>>> {
+        let mutating = method != Method::GET;
+
+        // The client sets the Host header from the uri
+        let mut req = Request::builder().uri(url).method(method);
+
+        if let Some(authorization) = self.auth.header() {
+            req = req.header(hyper::header::AUTHORIZATION, authorization);
+        }
+        for (name, value) in &self.headers {
+            req = req.header(name, value);
+        }
+        // Tunnelled https requests authenticate on CONNECT instead
+        if url.scheme_str() == Some("http") {
+            if let Some(auth) = self.proxies.for_uri(url).and_then(|p| p.auth()) {
+                req = req.header(hyper::header::PROXY_AUTHORIZATION, auth);
+            }
+        }
+        if mutating {
+            if let Some(crumb) = self.crumb.lock().await.as_ref() {
+                req = req.header(&crumb.field, &crumb.value);
+                if let Some(cookie) = &crumb.cookie {
+                    req = req.header(hyper::header::COOKIE, cookie);
+                }
+            }
+        }
+
+        let body = match content {
+            Some((content_type, data)) => {
+                req = req.header(hyper::header::CONTENT_TYPE, content_type);
+                Full::new(data)
+            }
+            None => Full::new(Bytes::new()),
+        };
+
+        Ok(req.body(body)?)
+    }
+
+    async fn execute(&self, req: Request<Full<Bytes>>) -> Result<Response<Body>> {
+        // Only idempotent requests are safe to send again
+        let retries = if req.method() == Method::GET {
+            self.retries
         } else {
-            // Currently 'https arm' of the `if` statement uses legacy tls implementation and
-            // should be switched in the future
-            let stream = HttpsConnector::new();
-            let sender = Client::builder(TokioExecutor::new()).build::<_, Empty<Bytes>>(stream);
+            0
+        };
+        let (parts, body) = req.into_parts();
+
+        let mut attempt = 0;
+        loop {
+            let mut req = Request::new(body.clone());
+            *req.method_mut() = parts.method.clone();
+            *req.uri_mut() = parts.uri.clone();
+            *req.headers_mut() = parts.headers.clone();
+
+            let res = self.execute_once(req).await;
+            let reason = match &res {
+                Ok(res) if RETRY_STATUSES.contains(&res.status()) => res.status().to_string(),
+                Err(e) if is_connection_reset(e.as_ref()) => e.to_string(),
+                _ => return res,
+            };
+            if attempt >= retries {
+                return res;
+            }
+
+            let delay = res
+                .as_ref()
+                .ok()
+                .and_then(retry_after)
+                .unwrap_or_else(|| backoff(attempt));
+            attempt += 1;
+            log::warn!(
+                "{} {}: {reason}, retry {attempt}/{retries} in {}ms",
+                parts.method,
+                parts.uri.path(),
+                delay.as_millis()
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    async fn execute_once(&self, req: Request<Full<Bytes>>) -> Result<Response<Body>> {
+        if !self.tracer.enabled() {
+            return self.exchange(req).await;
+        }
+
+        let method = req.method().to_string();
+        let url = req.uri().to_string();
+        self.tracer.request(&req).await;
+        let started = Instant::now();
+        let res = self.exchange(req).await;
+        self.tracer
+            .response(&method, &url, started.elapsed(), res)
+            .await
+    }
 
-            sender.request(req).await?
+    async fn exchange(&self, req: Request<Full<Bytes>>) -> Result<Response<Body>> {
+        let pending = match &self.recording {
+            Some(Recording::Replay(player)) => return player.respond(&req),
+            Some(Recording::Record(_)) => Some(Recorder::start(&req).await),
+            None => None,
         };
 
-        Ok(res)
+        let res = self.send(req).await?;
+        match (&self.recording, pending) {
+            (Some(Recording::Record(recorder)), Some(pending)) => recorder.save(pending, res).await,
+            _ => Ok(res),
+        }
+    }
+
+    async fn send(&self, req: Request<Full<Bytes>>) -> Result<Response<Body>> {
+        if let Some((interval, next)) = &self.throttle {
+            // The lock is held while waiting, so requests leave one by one
+            let mut next = next.lock().await;
+            tokio::time::sleep_until(*next).await;
+            *next = Instant::now() + *interval;
+        }
+
+        let Some(timeout) = self.timeout else {
+            return Ok(self
+                .client
+                .request(req)
+                .await?
+                .map(|body| body.map_err(Into::into).boxed()));
+        };
+
+        // Bounds the wait for the headers, then for every part of the body
+        let path = req.uri().path().to_string();
+        match tokio::time::timeout(timeout, self.client.request(req)).await {
+            Ok(res) => Ok(res?.map(|body| ReadTimeout::new(body, path, timeout).boxed())),
+            Err(_) => Err(format!(
+                "no response for {path} within {}s (see --timeout)",
+                timeout.as_secs_f64()
+            )
+            .into()),
+        }
+    }
+
+    async fn fetch_crumb(&self) -> Result<()> {
+        let url = self
+            .url
+            .join(&Tree::new("crumbIssuer/api/json".to_string()))?;
+        let req = self.request(&url, Method::GET, None).await?;
+        let res = self.execute(req).await?;
+        if !res.status().is_success() {
+            return Err(format!("failed to get a CSRF crumb: {}", res.status()).into());
+        }
+
+        let cookie = res
+            .headers()
+            .get_all(hyper::header::SET_COOKIE)
+            .iter()
+            .filter_map(|c| c.to_str().ok()?.split(';').next())
+            .collect::<Vec<_>>()
+            .join("; ");
+        let cookie = if cookie.is_empty() {
+            None
+        } else {
+            Some(HeaderValue::from_str(&cookie)?)
+        };
+
+        let body = res.collect().await?.to_bytes();
+        let info = serde_json::from_slice::<CrumbInfo>(&body)?;
+
+        *self.crumb.lock().await = Some(Crumb {
+            field: HeaderName::from_str(&info.crumb_request_field)?,
+            value: HeaderValue::from_str(&info.crumb)?,
+            cookie,
+        });
+
+        Ok(())
     }
 
     pub async fn get_json_data(&self, tree: &Tree) -> Result<tokio::io::BufWriter<Vec<u8>>> {
-        let url = format!("{}/{}", self.url, tree.query).parse::<hyper::Uri>()?;
-        let mut res = Self::send_request(&url, self.user, self.pswd, Method::GET).await?;
+        let url = self.url.join(tree)?;
+        let mut res = self.send_request(&url, Method::GET).await?;
 
         if res.status() == StatusCode::NOT_FOUND {
             return Err(res.status().as_str().into());
@@ -146,105 +632,81 @@ impl<'x> Jenkins<'x> {
         Ok(writer)
     }
 
-    pub async fn get_console_log(
-        &self,
-        tree: &Tree,
-    ) -> Option<(tokio::io::BufWriter<Vec<u8>>, usize)> {
-        let url = format!("{}/{}", self.url, tree.query)
-            .parse::<hyper::Uri>()
-            .ok()?;
-        let mut res = Self::send_request(&url, self.user, self.pswd, Method::GET)
-            .await
-            .ok()?;
+    pub async fn get(&self, tree: &Tree) -> Result<Response<Body>> {
+        let url = self.url.join(tree)?;
 
-        let offset = res
-            .headers()
-            .get("x-text-size")
-            .unwrap()
-            .to_str()
-            .ok()?
-            .parse::<usize>()
-            .ok()?;
+        self.send_request(&url, Method::GET).await
+    }
 
-        let buf = Vec::new();
-        let mut writer = tokio::io::BufWriter::new(buf);
+    /// Put the controller into quiet-down mode ("prepare for shutdown").
+    pub async fn quiet_down(&self, reason: &str) -> Result<Response<Body>> {
+        let tree = Tree::new("quietDown".to_string());
+        if reason.is_empty() {
+            return self.post(&tree).await;
+        }
 
-        while let Some(next) = res.frame().await {
-            let frame = next.ok()?;
-            if let Some(chunk) = frame.data_ref() {
-                writer.write_all(chunk).await.ok()?;
-            }
+        self.post(&tree.param("reason", reason)).await
+    }
+
+    pub async fn cancel_quiet_down(&self) -> Result<Response<Body>> {
+        self.post(&Tree::new("cancelQuietDown".to_string())).await
+    }
+
+    pub async fn restart(&self, hard: bool) -> Result<Response<Body>> {
+        if hard {
+            return self.post(&Tree::new("restart".to_string())).await;
         }
-        writer.flush().await.ok()?;
 
-        if !res.headers().contains_key("x-more-data") {
-            print!("{}", String::from_utf8_lossy(writer.get_ref()));
-            return None;
+        self.post(&Tree::new("safeRestart".to_string())).await
+    }
+
+    /// Copy a top-level job, `dest` is a job name (not a path).
+    pub async fn copy_job(&self, src: &str, dest: &str) -> Result<Response<Body>> {
+        if dest.contains('/') {
+            return Err(dest.into());
         }
 
-        Some((writer, offset))
+        self.copy(Tree::new("createItem".to_string()), src, dest)
+            .await
     }
 
-    pub async fn shutdown(self, state: ShutdownState) -> Result<Response<Incoming>> {
-        match state {
-            ShutdownState::On { reason } => {
-                if !reason.is_empty() {
-                    let url = format!("{}/quietDown?reason={}", self.url, encode(reason.as_str()))
-                        .parse::<hyper::Uri>()?;
-                    return Self::send_request(&url, self.user, self.pswd, Method::POST).await;
-                }
+    pub async fn copy_view(&self, src: &str, dest: &str) -> Result<Response<Body>> {
+        self.copy(Tree::new("createView".to_string()), src, dest)
+            .await
+    }
 
-                let url = format!("{}/quietDown", self.url).parse::<hyper::Uri>()?;
-                Self::send_request(&url, self.user, self.pswd, Method::POST).await
-            }
-            ShutdownState::Off => {
-                let url = format!("{}/cancelQuietDown", self.url).parse::<hyper::Uri>()?;
-                Self::send_request(&url, self.user, self.pswd, Method::POST).await
-            }
-        }
+    async fn copy(&self, tree: Tree, src: &str, dest: &str) -> Result<Response<Body>> {
+        self.post(
+            &tree
+                .param("from", src)
+                .param("mode", "copy")
+                .param("name", dest),
+        )
+        .await
     }
 
-    pub async fn restart(self, hard: bool) -> Result<Response<Incoming>> {
-        if hard {
-            let url = format!("{}/restart", self.url).parse::<hyper::Uri>()?;
-            return Self::send_request(&url, self.user, self.pswd, Method::POST).await;
-        }
+    pub async fn script(&self, tree: &Tree, script: &str) -> Result<Response<Body>> {
+        let url = self.url.join(tree)?;
+        let body = format!("script={}", encode(script));
 
-        let url = format!("{}/safeRestart", self.url).parse::<hyper::Uri>()?;
-        Self::send_request(&url, self.user, self.pswd, Method::POST).await
+        self.send_request_with_body(
+            &url,
+            Method::POST,
+            Some(("application/x-www-form-urlencoded", Bytes::from(body))),
+        )
+        .await
     }
 
-    pub async fn copy(
-        self,
-        item: CopyItem,
-        src: String,
-        dest: String,
-    ) -> Result<Response<Incoming>> {
-        match item {
-            CopyItem::Job => {
-                if dest.contains('/') {
-                    return Err(dest.into());
-                }
-                let url = format!(
-                    "{}/createItem?from={}&mode=copy&name={}",
-                    self.url,
-                    encode(src.as_str()),
-                    encode(dest.as_str())
-                )
-                .parse::<hyper::Uri>()?;
-                Self::send_request(&url, self.user, self.pswd, Method::POST).await
-            }
-            CopyItem::View => {
-                let url = format!(
-                    "{}/createView?from={}&mode=copy&name={}",
-                    self.url,
-                    encode(src.as_str()),
-                    encode(dest.as_str())
-                )
-                .parse::<hyper::Uri>()?;
-                Self::send_request(&url, self.user, self.pswd, Method::POST).await
+    pub async fn stream<W: Write>(mut res: Response<Body>, out: &mut W) -> Result<()> {
+        while let Some(next) = res.frame().await {
+            let frame = next?;
+            if let Some(chunk) = frame.data_ref() {
+                out.write_all(chunk)?;
+                out.flush()?;
             }
         }
+
+        Ok(())
     }
 
     pub fn system<'de, I>(json_data: &'de [u8]) -> Result<I>
@@ -256,110 +718,187 @@ impl<'x> Jenkins<'x> {
         Ok(info)
     }
 
-    pub async fn build(&self, job_path: &str, params: String) -> Result<Response<Incoming>> {
-        let path_components = std::path::Path::new(job_path)
-            .components()
-            .map(|e| "job/".to_string() + e.as_os_str().to_str().unwrap() + "/")
-            .collect::<String>();
+    /// Schedule a build, `None` builds a job without parameters, `Some`
+    /// a parameterized one (parameters not given keep their defaults).
+    pub async fn build(
+        &self,
+        job_path: &str,
+        params: Option<&[(String, String)]>,
+    ) -> Result<Response<Body>> {
+        let tree = match params {
+            None => Tree::new("build".to_string()),
+            Some(_) => Tree::new("buildWithParameters".to_string()),
+        };
+        let tree = params.unwrap_or_default().iter().fold(
+            tree.build_path(job_path).param("delay", "0sec"),
+            |tree, (name, value)| tree.param(name, value),
+        );
 
-        let url = match params.as_str() {
-            "" => {
-                format!("{}/{}build?delay=0sec", self.url, path_components).parse::<hyper::Uri>()?
-            }
-            "-" => format!(
-                "{}/{}buildWithParameters?delay=0sec",
-                self.url, path_components
-            )
-            .parse::<hyper::Uri>()?,
-            _ => {
-                let params = params
-                    .split(',')
-                    .map(|p| "&".to_string() + p)
-                    .collect::<String>();
-                format!(
-                    "{}/{}buildWithParameters?delay=0sec{}",
-                    self.url, path_components, params
-                )
-                .parse::<hyper::Uri>()?
-            }
+        self.post(&tree).await
+    }
+
+    pub async fn remove(&self, job_path: &str) -> Result<Response<Body>> {
+        let tree = Tree::new(String::new()).build_path(job_path).segment("");
+        let url = self.url.join(&tree)?;
+
+        self.send_request(&url, Method::DELETE).await
+    }
+
+    pub async fn kill(&self, tree: &Tree, signal: String) -> Result<Response<Body>> {
+        let action = match Signal::from_str(signal.as_str()) {
+            Ok(Signal::Hup) => "stop",
+            Ok(Signal::Term) => "term",
+            Ok(Signal::Kill) => "kill",
+            Err(e) => return Err(format!("invalid signal: {e}").into()),
         };
-        Self::send_request(&url, self.user, self.pswd, Method::POST).await
+
+        self.post(&tree.clone().segment(action)).await
+    }
+
+    pub async fn cancel(&self, id: u64) -> Result<Response<Body>> {
+        let tree = Tree::new("queue/cancelItem".to_string()).param("id", &id.to_string());
+
+        self.post(&tree).await
+    }
+
+    pub async fn create_node(
+        &self,
+        name: &str,
+        form: &serde_json::Value,
+    ) -> Result<Response<Body>> {
+        let url = self
+            .url
+            .join(&Tree::new("computer/doCreateItem".to_string()))?;
+        let body = format!(
+            "name={}&type=hudson.slaves.DumbSlave&json={}",
+            encode(name),
+            encode(form.to_string().as_str())
+        );
+
+        let res = self
+            .send_request_with_body(
+                &url,
+                Method::POST,
+                Some(("application/x-www-form-urlencoded", Bytes::from(body))),
+            )
+            .await?;
+
+        accepted(res, &format!("create node {name}"))
+    }
+
+    pub async fn post(&self, tree: &Tree) -> Result<Response<Body>> {
+        let url = self.url.join(tree)?;
+
+        self.send_request(&url, Method::POST).await
     }
 
-    pub async fn rebuild(&self, job_path: &str, params: String) -> Result<Response<Incoming>> {
-        let path_components = std::path::Path::new(job_path)
-            .components()
-            .map(|e| "job/".to_string() + e.as_os_str().to_str().unwrap() + "/")
+    pub async fn do_delete(&self, tree: &Tree) -> Result<Response<Body>> {
+        self.post(&tree.clone().segment("doDelete")).await
+    }
+
+    /// Post an xml configuration, an error unless the controller accepted it.
+    pub async fn post_config(&self, tree: &Tree, xml: String) -> Result<Response<Body>> {
+        let url = self.url.join(tree)?;
+
+        let res = self
+            .send_request_with_body(
+                &url,
+                Method::POST,
+                Some(("application/xml", Bytes::from(xml))),
+            )
+            .await?;
+
+        accepted(res, &format!("post {tree}"))
+    }
+
+    pub async fn install_plugins(&self, plugins: &[String]) -> Result<Response<Body>> {
+        let url = self.url.join(&Tree::new(
+            "pluginManager/installNecessaryPlugins".to_string(),
+        ))?;
+        let install = plugins
+            .iter()
+            .map(|p| match p.split_once('@') {
+                Some(_) => format!("<install plugin=\"{}\" />", xml::escape(p)),
+                None => format!("<install plugin=\"{}@latest\" />", xml::escape(p)),
+            })
             .collect::<String>();
 
-        let url = format!(
-            "{}/{}buildWithParameters?delay=0sec{}",
-            self.url,
-            path_components,
-            params.replace('"', "").replace(' ', "%20")
+        self.send_request_with_body(
+            &url,
+            Method::POST,
+            Some((
+                "text/xml",
+                Bytes::from(format!("<jenkins>{install}</jenkins>")),
+            )),
         )
-        .parse::<hyper::Uri>()?;
+        .await
+    }
+
+    pub async fn uninstall_plugin(&self, name: &str) -> Result<Response<Body>> {
+        let tree = Tree::new("pluginManager/plugin".to_string())
+            .segment(name)
+            .segment("doUninstall");
 
-        Self::send_request(&url, self.user, self.pswd, Method::POST).await
+        self.post(&tree).await
     }
 
-    pub async fn remove(self, job_path: &str) -> Result<Response<Incoming>> {
-        let path_components = std::path::Path::new(job_path)
-            .components()
-            .map(|e| "job/".to_string() + e.as_os_str().to_str().unwrap() + "/")
-            .collect::<String>();
+    pub async fn enable_plugin(&self, name: &str, enable: bool) -> Result<Response<Body>> {
+        let action = if enable {
+            "makeEnabled"
+        } else {
+            "makeDisabled"
+        };
+        let tree = Tree::new("pluginManager/plugin".to_string())
+            .segment(name)
+            .segment(action);
 
-        let url = format!("{}/{}", self.url, path_components).parse::<hyper::Uri>()?;
+        self.post(&tree).await
+    }
+
+    pub async fn generate_token(&self, name: &str) -> Result<Response<Body>> {
+        let url = self.url.join(&Tree::new(
+            "me/descriptorByName/jenkins.security.ApiTokenProperty/generateNewToken".to_string(),
+        ))?;
+        let body = format!("newTokenName={}", encode(name));
 
-        Self::send_request(&url, self.user, self.pswd, Method::DELETE).await
+        self.send_request_with_body(
+            &url,
+            Method::POST,
+            Some(("application/x-www-form-urlencoded", Bytes::from(body))),
+        )
+        .await
     }
 
-    pub async fn kill(&self, tree: &Tree, signal: String) -> Result<Response<Incoming>> {
-        if let Err(e) = Signal::from_str(signal.as_str()) {
-            return Err(format!("invalid signal: {e}").into());
-        }
+    pub async fn revoke_token(&self, uuid: &str) -> Result<Response<Body>> {
+        let tree =
+            Tree::new("me/descriptorByName/jenkins.security.ApiTokenProperty/revoke".to_string())
+                .param("tokenUuid", uuid);
+
+        self.post(&tree).await
+    }
 
-        let url = match Signal::from_str(signal.as_str())? {
-            Signal::Hup => format!("{}/{}/stop", self.url, tree.query),
-            Signal::Term => format!("{}/{}/term", self.url, tree.query),
-            Signal::Kill => format!("{}/{}/kill", self.url, tree.query),
+    /// Disconnect the agent of a node (`tree`: `computer/<NODE>`).
+    pub async fn disconnect(&self, tree: &Tree, reason: &str) -> Result<Response<Body>> {
+        let tree = tree.clone().segment("doDisconnect");
+        if reason.is_empty() {
+            return self.post(&tree).await;
         }
-        .parse::<hyper::Uri>()?;
 
-        Self::send_request(&url, self.user, self.pswd, Method::POST).await
+        self.post(&tree.param("offlineMessage", reason)).await
     }
 
-    pub async fn set(&self, tree: &Tree, state: NodeState) -> Result<Response<Incoming>> {
-        let url = match state {
-            NodeState::Disconnect { reason } => {
-                if reason.is_empty() {
-                    format!("{}{}/doDisconnect", self.url, tree.query)
-                } else {
-                    format!(
-                        "{}{}/doDisconnect?offlineMessage={}",
-                        self.url,
-                        tree.query,
-                        encode(reason.as_str())
-                    )
-                }
-            }
-            NodeState::Connect => format!("{}{}/launchSlaveAgent", self.url, tree.query),
-            NodeState::Offline { reason } => {
-                if reason.is_empty() {
-                    format!("{}{}/toggleOffline", self.url, tree.query)
-                } else {
-                    format!(
-                        "{}{}/toggleOffline?offlineMessage={}",
-                        self.url,
-                        tree.query,
-                        encode(reason.as_str())
-                    )
-                }
-            }
-            NodeState::Online => format!("{}{}/toggleOffline", self.url, tree.query),
+    pub async fn connect(&self, tree: &Tree) -> Result<Response<Body>> {
+        self.post(&tree.clone().segment("launchSlaveAgent")).await
+    }
+
+    /// Flip the temporarily offline state of a node, the reason is only
+    /// used when the node is taken offline.
+    pub async fn toggle_offline(&self, tree: &Tree, reason: &str) -> Result<Response<Body>> {
+        let tree = tree.clone().segment("toggleOffline");
+        if reason.is_empty() {
+            return self.post(&tree).await;
         }
-        .parse::<hyper::Uri>()?;
 
-        Self::send_request(&url, self.user, self.pswd, Method::POST).await
+        self.post(&tree.param("offlineMessage", reason)).await
     }
 }
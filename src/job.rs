@@ -30,7 +30,7 @@ pub struct Build {
     #[serde(rename = "_class")]
     class: String,
     pub number: u32,
-    url: String,
+    pub url: String,
 }
 
 #[derive(Deserialize, Debug, Serialize)]
@@ -61,3 +61,85 @@ pub struct ParamsAction {
     pub name: String,
     pub value: serde_json::value::Value,
 }
+
+/// Turn a job url (`https://host/job/a/job/b/`) into a job path (`a/b`).
+pub fn path_from_url(url: &str) -> String {
+    let mut path = Vec::new();
+    let mut segments = url.split('/');
+
+    while let Some(segment) = segments.next() {
+        if segment == "job" {
+            if let Some(name) = segments.next() {
+                let name = urlencoding::decode(name)
+                    .map_or_else(|_| name.to_string(), std::borrow::Cow::into_owned);
+                path.push(name);
+            }
+        }
+    }
+
+    path.join("/")
+}
+
+/// Human readable job status from the ball color Jenkins reports.
+pub fn status(color: &str) -> &str {
+    if color.ends_with("_anime") {
+        return "running";
+    }
+
+    match color {
+        "blue" => "success",
+        "red" => "failed",
+        "yellow" => "unstable",
+        "aborted" => "aborted",
+        "disabled" => "disabled",
+        "notbuilt" => "not built",
+        _ => "unknown",
+    }
+}
+
+/// Edit distance between two strings, in characters.
+fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+/// Up to three of the `candidates` job paths that `path` likely meant: same
+/// job name in another folder, paths containing it, then typos.
+pub fn similar<'a>(path: &str, candidates: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let path = path.trim_matches('/').to_lowercase();
+    let name = path.rsplit('/').next().unwrap_or_default();
+    let max_distance = (path.chars().count() / 3).max(2);
+
+    let mut matches = candidates
+        .filter_map(|candidate| {
+            let lower = candidate.to_lowercase();
+            let distance = distance(&path, &lower);
+            let rank = if lower.rsplit('/').next() == Some(name) {
+                0
+            } else if lower.contains(&path) {
+                1
+            } else if distance <= max_distance {
+                2
+            } else {
+                return None;
+            };
+
+            Some(((rank, distance), candidate.to_string()))
+        })
+        .collect::<Vec<_>>();
+    matches.sort();
+
+    matches.into_iter().take(3).map(|(_, path)| path).collect()
+}
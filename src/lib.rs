@@ -0,0 +1,47 @@
+#![warn(clippy::all, clippy::pedantic)]
+#![allow(clippy::missing_errors_doc, clippy::must_use_candidate)]
+//! Jenkins management library, the engine of the `jenkinsctl` tool.
+//!
+//! `JenkinsClient` has typed methods for jobs, builds, nodes and build
+//! logs, the lower-level `Jenkins` client reaches the other endpoints:
+//!
+//! ```no_run
+//! use jenkinsctl::{auth, jenkins::ClientOptions, JenkinsClient};
+//!
+//! # async fn run() -> jenkinsctl::Result<()> {
+//! let credentials = auth::Plain {
+//!     user: "admin".to_string(),
+//!     token: "...".to_string(),
+//! };
+//! let client = JenkinsClient::new("https://ci.example.com", &credentials, ClientOptions::default())?;
+//!
+//! for job in client.jobs().await? {
+//!     println!("{}", job.full_name);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+pub mod auth;
+pub mod cache;
+pub mod client;
+pub mod config;
+pub mod credentials;
+pub mod human;
+pub mod jenkins;
+pub mod job;
+pub mod node;
+pub mod plugin;
+pub mod proxy;
+pub mod queue;
+pub mod recording;
+pub mod selector;
+pub mod tls;
+pub mod trace;
+pub mod url;
+pub mod user;
+pub mod view;
+pub mod xml;
+
+pub use client::JenkinsClient;
+
+pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
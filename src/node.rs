@@ -1,6 +1,8 @@
 #![allow(clippy::struct_excessive_bools, clippy::struct_field_names)]
 use serde::{Deserialize, Serialize};
 
+use crate::human;
+
 #[derive(Deserialize, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Info {
@@ -13,23 +15,22 @@ pub struct Info {
 #[derive(Deserialize, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Computer {
-    //action: Option<Action>,
     assigned_labels: Vec<AssignedLabels>,
     description: Option<String>,
     pub display_name: String,
     //executors: Vec<String>,
     icon: String,
     icon_class_name: String,
-    idle: bool,
+    pub idle: bool,
     jnlp_agent: bool,
     launch_supported: bool,
     manual_launch_allowed: bool,
     monitor_data: MonitorData,
     num_executors: u32,
     pub offline: bool,
-    //offline_cause: Option<String>,
+    offline_cause: Option<OfflineCause>,
     offline_cause_reason: String,
-    temporarily_offline: bool,
+    pub temporarily_offline: bool,
     //one_off_executors: Vec<String>,
     absolute_remote_path: Option<String>,
 }
@@ -41,8 +42,36 @@ impl std::fmt::Display for Computer {
     }
 }
 
+/// Name and labels of a node, the part of the node list that does not
+/// change with its state.
 #[derive(Deserialize, Debug, Serialize)]
-struct Action;
+pub struct Labels {
+    pub name: String,
+    labels: Vec<String>,
+}
+
+impl Labels {
+    pub fn labels(&self) -> Vec<&str> {
+        self.labels.iter().map(String::as_str).collect()
+    }
+}
+
+impl From<&Computer> for Labels {
+    fn from(c: &Computer) -> Self {
+        Self {
+            name: c.display_name.clone(),
+            labels: c.assigned_labels.iter().map(|l| l.name.clone()).collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+struct OfflineCause {
+    #[serde(rename = "_class")]
+    class: String,
+    description: Option<String>,
+    timestamp: Option<u64>,
+}
 
 #[derive(Deserialize, Debug, Serialize)]
 struct AssignedLabels {
@@ -105,3 +134,212 @@ struct ResponseTimeMonitor {
 struct ClockMonitor {
     diff: i64,
 }
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct Load {
+    pub computer: Vec<ComputerLoad>,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComputerLoad {
+    pub display_name: String,
+    pub executors: Vec<Executor>,
+    pub one_off_executors: Vec<Executor>,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Executor {
+    pub number: u32,
+    pub current_executable: Option<Executable>,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Executable {
+    pub number: Option<u32>,
+    pub url: Option<String>,
+    pub timestamp: Option<u64>,
+    pub estimated_duration: Option<i64>,
+}
+
+/// Limits used to flag unhealthy nodes, sizes are in bytes and times in
+/// milliseconds.
+pub struct Thresholds {
+    pub min_disk: u64,
+    pub min_temp: u64,
+    pub min_memory: u64,
+    pub max_clock_drift: u64,
+    pub max_response_time: u64,
+}
+
+impl Computer {
+    pub fn offline_status(&self) -> &str {
+        if self.temporarily_offline {
+            "temporarily offline"
+        } else if self.offline {
+            "disconnected"
+        } else {
+            "online"
+        }
+    }
+
+    pub fn offline_reason(&self) -> &str {
+        self.offline_cause_reason.as_str()
+    }
+
+    /// Who or what took the node offline. User causes carry the user name in
+    /// their description (`Disconnected by <user> : <reason>`), other causes
+    /// are reported by their class name.
+    pub fn offline_by(&self) -> Option<String> {
+        let cause = self.offline_cause.as_ref()?;
+        let kind = cause
+            .class
+            .rsplit_once('$')
+            .map_or(cause.class.as_str(), |c| c.1);
+
+        if kind == "UserCause" {
+            let description = cause.description.as_deref()?;
+            let user = description.split_once(" by ")?.1;
+            let user = user.split_once(" : ").map_or(user, |u| u.0);
+            return Some(user.trim().to_string());
+        }
+
+        Some(kind.to_string())
+    }
+
+    /// Offline cause timestamp in milliseconds since the unix epoch.
+    pub fn offline_since(&self) -> Option<u64> {
+        self.offline_cause.as_ref()?.timestamp
+    }
+
+    pub fn labels(&self) -> Vec<&str> {
+        self.assigned_labels
+            .iter()
+            .map(|l| l.name.as_str())
+            .collect()
+    }
+
+    /// Human readable monitor data as (name, value) pairs.
+    pub fn report(&self) -> Vec<(&'static str, String)> {
+        let m = &self.monitor_data;
+        let na = || "n/a".to_string();
+
+        vec![
+            (
+                "disk",
+                m.disk_space_monitor.as_ref().map_or_else(na, |d| {
+                    format!("{} free ({})", human::bytes(d.size), d.path)
+                }),
+            ),
+            (
+                "temp",
+                m.temporary_space_monitor.as_ref().map_or_else(na, |t| {
+                    format!("{} free ({})", human::bytes(t.size), t.path)
+                }),
+            ),
+            (
+                "memory",
+                m.swap_space_monitor.as_ref().map_or_else(na, |s| {
+                    format!(
+                        "{} / {}",
+                        human::bytes(s.available_physical_memory),
+                        human::bytes(s.total_physical_memory)
+                    )
+                }),
+            ),
+            (
+                "swap",
+                m.swap_space_monitor.as_ref().map_or_else(na, |s| {
+                    format!(
+                        "{} / {}",
+                        human::bytes(s.available_swap_space),
+                        human::bytes(s.total_swap_space)
+                    )
+                }),
+            ),
+            (
+                "response time",
+                m.response_time_monitor
+                    .as_ref()
+                    .map_or_else(na, |r| format!("{}ms", r.average)),
+            ),
+            (
+                "clock drift",
+                m.clock_monitor
+                    .as_ref()
+                    .map_or_else(na, |c| format!("{:+}ms", c.diff)),
+            ),
+            (
+                "architecture",
+                m.architecture_monitor.clone().unwrap_or_else(na),
+            ),
+        ]
+    }
+
+    /// Problems found in the monitor data according to the thresholds.
+    pub fn warnings(&self, t: &Thresholds) -> Vec<String> {
+        let m = &self.monitor_data;
+        let mut warnings = Vec::new();
+
+        if self.offline {
+            warnings.push("node is offline".to_string());
+        }
+        if let Some(d) = m
+            .disk_space_monitor
+            .as_ref()
+            .filter(|d| d.size < t.min_disk)
+        {
+            warnings.push(format!(
+                "free disk space {} is below {}",
+                human::bytes(d.size),
+                human::bytes(t.min_disk)
+            ));
+        }
+        if let Some(d) = m
+            .temporary_space_monitor
+            .as_ref()
+            .filter(|d| d.size < t.min_temp)
+        {
+            warnings.push(format!(
+                "free temp space {} is below {}",
+                human::bytes(d.size),
+                human::bytes(t.min_temp)
+            ));
+        }
+        if let Some(s) = m
+            .swap_space_monitor
+            .as_ref()
+            .filter(|s| s.available_physical_memory < t.min_memory)
+        {
+            warnings.push(format!(
+                "available memory {} is below {}",
+                human::bytes(s.available_physical_memory),
+                human::bytes(t.min_memory)
+            ));
+        }
+        if let Some(r) = m
+            .response_time_monitor
+            .as_ref()
+            .filter(|r| u64::from(r.average) > t.max_response_time)
+        {
+            warnings.push(format!(
+                "response time {}ms is over {}ms",
+                r.average, t.max_response_time
+            ));
+        }
+        if let Some(c) = m
+            .clock_monitor
+            .as_ref()
+            .filter(|c| c.diff.unsigned_abs() > t.max_clock_drift)
+        {
+            warnings.push(format!(
+                "clock drift {:+}ms is over {}ms",
+                c.diff, t.max_clock_drift
+            ));
+        }
+
+        warnings
+    }
+}
@@ -0,0 +1,28 @@
+#![allow(clippy::struct_excessive_bools)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct Info {
+    pub plugins: Vec<Plugin>,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Plugin {
+    pub short_name: String,
+    pub version: String,
+    pub active: bool,
+    pub enabled: bool,
+    #[serde(default)]
+    pub pinned: bool,
+    pub has_update: bool,
+    pub dependencies: Vec<Dependency>,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Dependency {
+    pub short_name: String,
+    pub version: String,
+    pub optional: bool,
+}
@@ -0,0 +1,63 @@
+#![allow(clippy::struct_excessive_bools)]
+use serde::{Deserialize, Serialize};
+
+use crate::job;
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct Info {
+    pub items: Vec<Item>,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Item {
+    #[serde(rename = "_class")]
+    class: String,
+    pub id: u64,
+    pub why: Option<String>,
+    pub in_queue_since: u64,
+    pub stuck: bool,
+    pub blocked: bool,
+    pub buildable: bool,
+    pub task: Task,
+    /// Set once the item left the queue by being cancelled.
+    #[serde(default)]
+    pub cancelled: bool,
+    /// Build started for the item, once it left the queue.
+    #[serde(default)]
+    pub executable: Option<Executable>,
+}
+
+impl Item {
+    /// Job path of the queued task (format: path/to/jenkins/job).
+    pub fn path(&self) -> String {
+        match &self.task.url {
+            Some(url) => job::path_from_url(url),
+            None => self.task.name.clone(),
+        }
+    }
+
+    pub fn state(&self) -> &str {
+        if self.stuck {
+            "stuck"
+        } else if self.blocked {
+            "blocked"
+        } else if self.buildable {
+            "buildable"
+        } else {
+            "waiting"
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct Task {
+    pub name: String,
+    pub url: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct Executable {
+    pub number: u32,
+    pub url: String,
+}
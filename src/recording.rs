@@ -0,0 +1,267 @@
+//! Recording of the HTTP exchanges with the controller (`--record <DIR>`)
+//! and their replay instead of the network (`--replay <DIR>`), so a session
+//! can be attached to a bug report or reused as a test fixture.
+//!
+//! Every exchange is saved as a json file named after its sequence number,
+//! method and path. Credentials headers (`Authorization`, cookies, the CSRF
+//! crumb) are redacted, secrets of the urls and text bodies are masked as
+//! when tracing, and so is the script of `script run`, which may embed any
+//! credential.
+use base64::Engine as _;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::{header::HeaderMap, Request, Response};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+};
+
+use crate::{jenkins::Body, trace, Result};
+
+const REDACTED: &str = "REDACTED";
+/// Longest path part of a recording file name.
+const MAX_SLUG: usize = 60;
+
+#[derive(Serialize, Deserialize)]
+struct Message {
+    headers: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    body: String,
+    /// Body that is not valid UTF-8.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    body_base64: String,
+}
+
+/// Form body with the script of the script console masked.
+fn mask_script(form: &str) -> String {
+    form.split('&')
+        .map(|param| {
+            if param.starts_with("script=") {
+                format!("script={REDACTED}")
+            } else {
+                param.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+impl Message {
+    fn new(headers: &HeaderMap, body: &Bytes) -> Self {
+        let content_type = headers
+            .get(hyper::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+
+        let headers = headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if trace::is_secret_header(name.as_str()) {
+                    REDACTED.to_string()
+                } else {
+                    String::from_utf8_lossy(value.as_bytes()).into_owned()
+                };
+                (name.to_string(), value)
+            })
+            .collect();
+
+        let (body, body_base64) = match std::str::from_utf8(body) {
+            Ok(text) if content_type.contains("x-www-form-urlencoded") => (
+                mask_script(&trace::mask_body(content_type, text)),
+                String::new(),
+            ),
+            Ok(text) => (trace::mask_body(content_type, text), String::new()),
+            Err(_) => (
+                String::new(),
+                base64::engine::general_purpose::STANDARD.encode(body),
+            ),
+        };
+
+        Self {
+            headers,
+            body,
+            body_base64,
+        }
+    }
+
+    fn body(&self) -> Result<Bytes> {
+        if self.body_base64.is_empty() {
+            return Ok(Bytes::from(self.body.clone()));
+        }
+
+        Ok(base64::engine::general_purpose::STANDARD
+            .decode(&self.body_base64)?
+            .into())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Exchange {
+    method: String,
+    /// Path and query of the request.
+    url: String,
+    request: Message,
+    status: u16,
+    response: Message,
+}
+
+fn body(data: Bytes) -> Body {
+    Full::new(data).map_err(|never| match never {}).boxed()
+}
+
+/// A request being sent, saved once its response arrives.
+pub struct Pending {
+    method: String,
+    url: String,
+    request: Message,
+}
+
+pub struct Recorder {
+    dir: PathBuf,
+    count: Mutex<usize>,
+}
+
+impl Recorder {
+    pub fn new(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("failed to create {}: {e}", dir.display()))?;
+        if !recordings(dir)?.is_empty() {
+            return Err(format!("{} already holds a recording", dir.display()).into());
+        }
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            count: Mutex::new(0),
+        })
+    }
+
+    pub async fn start(req: &Request<Full<Bytes>>) -> Pending {
+        let body = req
+            .body()
+            .clone()
+            .collect()
+            .await
+            .map_or_else(|never| match never {}, http_body_util::Collected::to_bytes);
+
+        Pending {
+            method: req.method().to_string(),
+            url: trace::mask_url(req.uri().path_and_query().map_or("/", |p| p.as_str())),
+            request: Message::new(req.headers(), &body),
+        }
+    }
+
+    /// Save the exchange, the response body is read in full and handed back
+    /// with the response.
+    pub async fn save(&self, pending: Pending, res: Response<Body>) -> Result<Response<Body>> {
+        let (parts, data) = res.into_parts();
+        let data = data.collect().await?.to_bytes();
+
+        let exchange = Exchange {
+            method: pending.method,
+            url: pending.url,
+            request: pending.request,
+            status: parts.status.as_u16(),
+            response: Message::new(&parts.headers, &data),
+        };
+
+        let n = {
+            let mut count = self.count.lock().unwrap_or_else(PoisonError::into_inner);
+            *count += 1;
+            *count
+        };
+        let slug = exchange
+            .url
+            .split('?')
+            .next()
+            .unwrap_or_default()
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join("-");
+        let slug = slug.get(..MAX_SLUG).unwrap_or(&slug);
+        let path = self
+            .dir
+            .join(format!("{n:06}-{}-{slug}.json", exchange.method));
+        tokio::fs::write(&path, serde_json::to_vec_pretty(&exchange)?)
+            .await
+            .map_err(|e| format!("failed to write {}: {e}", path.display()))?;
+
+        Ok(Response::from_parts(parts, body(data)))
+    }
+}
+
+pub struct Player {
+    dir: PathBuf,
+    exchanges: Vec<Exchange>,
+    used: Mutex<Vec<bool>>,
+}
+
+impl Player {
+    pub fn load(dir: &Path) -> Result<Self> {
+        let mut exchanges = Vec::new();
+        for path in recordings(dir)? {
+            let data = std::fs::read(&path)?;
+            exchanges.push(
+                serde_json::from_slice::<Exchange>(&data)
+                    .map_err(|e| format!("invalid recording {}: {e}", path.display()))?,
+            );
+        }
+        if exchanges.is_empty() {
+            return Err(format!("no recording found in {}", dir.display()).into());
+        }
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            used: Mutex::new(vec![false; exchanges.len()]),
+            exchanges,
+        })
+    }
+
+    /// The first recorded response to the same method and url that was not
+    /// replayed yet.
+    pub fn respond(&self, req: &Request<Full<Bytes>>) -> Result<Response<Body>> {
+        let method = req.method().as_str();
+        // Recorded urls have their secrets masked
+        let url = trace::mask_url(req.uri().path_and_query().map_or("/", |p| p.as_str()));
+
+        let exchange = {
+            let mut used = self.used.lock().unwrap_or_else(PoisonError::into_inner);
+            let Some(i) = (0..self.exchanges.len()).find(|&i| {
+                !used[i] && self.exchanges[i].method == method && self.exchanges[i].url == url
+            }) else {
+                return Err(format!(
+                    "no recorded response for {method} {url} in {}",
+                    self.dir.display()
+                )
+                .into());
+            };
+            used[i] = true;
+            &self.exchanges[i]
+        };
+
+        let mut res = Response::builder().status(exchange.status);
+        for (name, value) in &exchange.response.headers {
+            res = res.header(name, value);
+        }
+
+        Ok(res.body(body(exchange.response.body()?))?)
+    }
+}
+
+/// Recording files of a directory in the order they were made.
+fn recordings(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = std::fs::read_dir(dir)
+        .map_err(|e| format!("failed to read {}: {e}", dir.display()))?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().map_or(false, |e| e == "json"))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    Ok(paths)
+}
+
+pub enum Recording {
+    Record(Recorder),
+    Replay(Player),
+}
@@ -0,0 +1,223 @@
+//! Node selector expressions, e.g. `label=linux && !gpu`.
+//!
+//! A term is either `label=NAME` (or just `NAME`), `label!=NAME`,
+//! `name=NODE` or `name!=NODE`. Terms can be combined with `!`, `&&`, `||` and parentheses,
+//! `&&` binds tighter than `||`.
+use std::str::FromStr;
+
+#[derive(Clone, Debug)]
+enum Expr {
+    Label(String),
+    Name(String),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Not,
+    And,
+    Or,
+    Open,
+    Close,
+}
+
+#[derive(Clone, Debug)]
+pub struct Selector(Expr);
+
+impl Selector {
+    pub fn matches(&self, name: &str, labels: &[&str]) -> bool {
+        Self::eval(&self.0, name, labels)
+    }
+
+    fn eval(expr: &Expr, name: &str, labels: &[&str]) -> bool {
+        match expr {
+            Expr::Label(l) => labels.contains(&l.as_str()),
+            Expr::Name(n) => n == name,
+            Expr::Not(e) => !Self::eval(e, name, labels),
+            Expr::And(a, b) => Self::eval(a, name, labels) && Self::eval(b, name, labels),
+            Expr::Or(a, b) => Self::eval(a, name, labels) || Self::eval(b, name, labels),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            '&' | '|' => {
+                if chars.next() != Some(c) {
+                    return Err(format!("expected '{c}{c}' in '{s}'"));
+                }
+                tokens.push(if c == '&' { Token::And } else { Token::Or });
+            }
+            '!' => tokens.push(Token::Not),
+            _ => {
+                let mut word = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    // `!=` belongs to the term, a lone `!` negates what follows
+                    let not_equal = c == '!' && chars.clone().nth(1) == Some('=');
+                    if c.is_whitespace() || "()&|".contains(c) || (c == '!' && !not_equal) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Not) => Ok(Expr::Not(Box::new(self.unary()?))),
+            Some(Token::Open) => {
+                let expr = self.or()?;
+                if self.next() != Some(Token::Close) {
+                    return Err("missing ')'".to_string());
+                }
+                Ok(expr)
+            }
+            Some(Token::Word(word)) => Self::term(&word),
+            Some(token) => Err(format!("unexpected {token:?}")),
+            None => Err("unexpected end of selector".to_string()),
+        }
+    }
+
+    fn term(word: &str) -> Result<Expr, String> {
+        if let Some(label) = word.strip_prefix("label!=") {
+            return Ok(Expr::Not(Box::new(Expr::Label(label.to_string()))));
+        }
+        if let Some(label) = word.strip_prefix("label=") {
+            return Ok(Expr::Label(label.to_string()));
+        }
+        if let Some(name) = word.strip_prefix("name!=") {
+            return Ok(Expr::Not(Box::new(Expr::Name(name.to_string()))));
+        }
+        if let Some(name) = word.strip_prefix("name=") {
+            return Ok(Expr::Name(name.to_string()));
+        }
+        if word.contains('=') {
+            return Err(format!("unknown selector term '{word}'"));
+        }
+
+        Ok(Expr::Label(word.to_string()))
+    }
+}
+
+impl FromStr for Selector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+        };
+        let expr = parser.or()?;
+
+        if parser.pos != parser.tokens.len() {
+            return Err(format!("unexpected trailing input in '{s}'"));
+        }
+
+        Ok(Self(expr))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(selector: &str, name: &str, labels: &[&str]) -> bool {
+        selector.parse::<Selector>().unwrap().matches(name, labels)
+    }
+
+    fn error(selector: &str) -> String {
+        selector.parse::<Selector>().unwrap_err()
+    }
+
+    #[test]
+    fn terms() {
+        assert!(matches("linux", "agent-1", &["linux", "docker"]));
+        assert!(matches("label=linux", "agent-1", &["linux"]));
+        assert!(!matches("label=linux", "agent-1", &["windows"]));
+        assert!(matches("label!=gpu", "agent-1", &["linux"]));
+        assert!(!matches("label!=gpu", "agent-1", &["gpu"]));
+        assert!(matches("name=agent-1", "agent-1", &[]));
+        assert!(!matches("name=agent-1", "agent-2", &[]));
+        assert!(matches("name!=agent-1", "agent-2", &[]));
+        assert!(!matches("name!=agent-1", "agent-1", &[]));
+    }
+
+    #[test]
+    fn precedence() {
+        // `!` binds tighter than `&&`, which binds tighter than `||`
+        assert!(matches("!gpu && linux", "a", &["linux"]));
+        assert!(!matches("!gpu && linux", "a", &["gpu", "linux"]));
+        assert!(matches("a || b && c", "n", &["a"]));
+        assert!(!matches("(a || b) && c", "n", &["a"]));
+        assert!(matches("!(a || b)", "n", &["c"]));
+        assert!(!matches("!(a || b)", "n", &["b"]));
+        assert!(matches("!!a", "n", &["a"]));
+        assert!(matches("linux&&!gpu", "n", &["linux"]));
+        assert!(matches(
+            "name=built-in || (label=linux && label!=gpu)",
+            "agent-1",
+            &["linux"]
+        ));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(error("linux &"), "expected '&&' in 'linux &'");
+        assert_eq!(error("(linux"), "missing ')'");
+        assert_eq!(error("linux &&"), "unexpected end of selector");
+        assert_eq!(error("linux)"), "unexpected trailing input in 'linux)'");
+        assert_eq!(error("&& linux"), "unexpected And");
+        assert_eq!(error("os=linux"), "unknown selector term 'os=linux'");
+        assert_eq!(error("os!=linux"), "unknown selector term 'os!=linux'");
+    }
+}
@@ -0,0 +1,308 @@
+//! Tracing of the HTTP exchanges with the controller (`-v`, `-vv`, `-vvv`):
+//! the method, url, status and latency of every request, then the headers,
+//! then the bodies. A summary of the slowest calls is logged once the client
+//! is dropped, i.e. at the end of a command.
+//!
+//! Credential headers, the CSRF crumb and password or token looking
+//! parameters of urls and bodies are masked, by the same helpers that mask
+//! the recordings (`--record`).
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::{header::HeaderMap, Request, Response};
+use std::{
+    sync::{Mutex, PoisonError},
+    time::Duration,
+};
+
+use crate::{jenkins::Body, Result};
+
+/// Level logging the request line with the status and latency.
+pub const REQUESTS: u8 = 1;
+/// Level logging the headers as well.
+pub const HEADERS: u8 = 2;
+/// Level logging the text bodies as well.
+pub const BODIES: u8 = 3;
+
+/// Headers whose values are never shown, besides the CSRF crumb.
+const SECRET_HEADERS: [&str; 4] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+];
+/// Parameter, field and tag names holding a secret.
+const SECRET_NAMES: [&str; 6] = [
+    "password",
+    "passwd",
+    "passphrase",
+    "token",
+    "secret",
+    "privatekey",
+];
+const MASK: &str = "***";
+/// Longest part of a body logged.
+const MAX_BODY: usize = 4096;
+/// Number of calls listed in the summary.
+const SLOWEST: usize = 5;
+
+/// Whether the value of a header must not be logged or saved.
+pub fn is_secret_header(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SECRET_HEADERS.contains(&name.as_str()) || name.contains("crumb")
+}
+
+fn is_secret_name(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name == "crumb" || SECRET_NAMES.iter().any(|s| name.contains(s))
+}
+
+/// Url with the values of the secret query parameters masked.
+pub fn mask_url(url: &str) -> String {
+    match url.split_once('?') {
+        Some((path, query)) => format!("{path}?{}", mask_params(query)),
+        None => url.to_string(),
+    }
+}
+
+/// `name=value&...` with the values of the secret parameters masked.
+fn mask_params(params: &str) -> String {
+    params
+        .split('&')
+        .map(|param| match param.split_once('=') {
+            Some((name, _)) if is_secret_name(&urlencoding::decode(name).unwrap_or_default()) => {
+                format!("{name}={MASK}")
+            }
+            _ => param.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn mask_json(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (name, value) in fields {
+                if is_secret_name(name) && (value.is_string() || value.is_number()) {
+                    *value = MASK.into();
+                } else {
+                    mask_json(value);
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(mask_json),
+        _ => {}
+    }
+}
+
+/// Xml with the text of the secret elements masked.
+fn mask_xml(xml: &str) -> String {
+    let mut masked = String::with_capacity(xml.len());
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        let Some(end) = rest[start..].find('>').map(|end| start + end + 1) else {
+            break;
+        };
+        masked.push_str(&rest[..end]);
+        let tag = &rest[start + 1..end - 1];
+        rest = &rest[end..];
+
+        let name = tag.split_whitespace().next().unwrap_or_default();
+        if tag.ends_with('/') || name.starts_with(['/', '?', '!']) || !is_secret_name(name) {
+            continue;
+        }
+        if let Some(text) = rest.find("</") {
+            masked.push_str(MASK);
+            rest = &rest[text..];
+        }
+    }
+    masked.push_str(rest);
+
+    masked
+}
+
+/// Body text with its secrets masked according to its content type.
+pub fn mask_body(content_type: &str, body: &str) -> String {
+    if content_type.contains("json") {
+        if let Ok(mut value) = serde_json::from_str(body) {
+            mask_json(&mut value);
+            return value.to_string();
+        }
+    }
+    if content_type.contains("xml") {
+        return mask_xml(body);
+    }
+    if content_type.contains("x-www-form-urlencoded") {
+        return mask_params(body);
+    }
+
+    body.to_string()
+}
+
+fn content_type(headers: &HeaderMap) -> &str {
+    headers
+        .get(hyper::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+}
+
+fn is_text(content_type: &str) -> bool {
+    [
+        "text/",
+        "json",
+        "xml",
+        "x-www-form-urlencoded",
+        "javascript",
+    ]
+    .iter()
+    .any(|t| content_type.contains(t))
+}
+
+fn log_headers(prefix: char, headers: &HeaderMap) {
+    for (name, value) in headers {
+        let value = if is_secret_header(name.as_str()) {
+            MASK.into()
+        } else {
+            String::from_utf8_lossy(value.as_bytes())
+        };
+        log::info!("{prefix} {name}: {value}");
+    }
+}
+
+fn log_body(prefix: char, content_type: &str, body: &[u8]) {
+    if body.is_empty() {
+        return;
+    }
+    if !is_text(content_type) {
+        log::info!("{prefix} <{} bytes of {content_type}>", body.len());
+        return;
+    }
+
+    let body = mask_body(content_type, &String::from_utf8_lossy(body));
+    let mut end = body.len().min(MAX_BODY);
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    for line in body[..end].lines() {
+        log::info!("{prefix} {line}");
+    }
+    if end < body.len() {
+        log::info!("{prefix} ... ({} bytes)", body.len());
+    }
+}
+
+struct Call {
+    method: String,
+    url: String,
+    status: Option<u16>,
+    elapsed: Duration,
+}
+
+pub struct Tracer {
+    level: u8,
+    calls: Mutex<Vec<Call>>,
+}
+
+impl Tracer {
+    pub fn new(level: u8) -> Self {
+        Self {
+            level,
+            calls: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.level >= REQUESTS
+    }
+
+    /// Log the headers and body of a request about to be sent.
+    pub async fn request(&self, req: &Request<Full<Bytes>>) {
+        if self.level >= HEADERS {
+            log::info!("> {} {}", req.method(), mask_url(&req.uri().to_string()));
+            log_headers('>', req.headers());
+        }
+        if self.level >= BODIES {
+            let body = req
+                .body()
+                .clone()
+                .collect()
+                .await
+                .map_or_else(|never| match never {}, http_body_util::Collected::to_bytes);
+            log_body('>', content_type(req.headers()), &body);
+        }
+    }
+
+    /// Log the outcome of a request sent `elapsed` ago, text bodies are read
+    /// in full to be logged and handed back with the response.
+    pub async fn response(
+        &self,
+        method: &str,
+        url: &str,
+        elapsed: Duration,
+        res: Result<Response<Body>>,
+    ) -> Result<Response<Body>> {
+        let url = mask_url(url);
+        let status = res.as_ref().ok().map(|res| res.status().as_u16());
+        match &res {
+            Ok(res) => log::info!("{method} {url} {} {}ms", res.status(), elapsed.as_millis()),
+            Err(e) => log::info!("{method} {url} failed after {}ms: {e}", elapsed.as_millis()),
+        }
+        self.calls
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Call {
+                method: method.to_string(),
+                url,
+                status,
+                elapsed,
+            });
+
+        let res = res?;
+        if self.level >= HEADERS {
+            log_headers('<', res.headers());
+        }
+        let content_type = content_type(res.headers()).to_string();
+        if self.level < BODIES || !is_text(&content_type) {
+            return Ok(res);
+        }
+
+        let (parts, data) = res.into_parts();
+        let data = data.collect().await?.to_bytes();
+        log_body('<', &content_type, &data);
+
+        Ok(Response::from_parts(
+            parts,
+            Full::new(data).map_err(|never| match never {}).boxed(),
+        ))
+    }
+}
+
+impl Drop for Tracer {
+    fn drop(&mut self) {
+        if !self.enabled() {
+            return;
+        }
+        let calls = self.calls.get_mut().unwrap_or_else(PoisonError::into_inner);
+        if calls.is_empty() {
+            return;
+        }
+
+        let total = calls.iter().map(|c| c.elapsed).sum::<Duration>();
+        log::info!(
+            "{} requests in {}ms, slowest:",
+            calls.len(),
+            total.as_millis()
+        );
+        calls.sort_by_key(|c| std::cmp::Reverse(c.elapsed));
+        for call in calls.iter().take(SLOWEST) {
+            let status = call
+                .status
+                .map_or_else(|| "failed".to_string(), |s| s.to_string());
+            log::info!(
+                "{:>8}ms {status} {} {}",
+                call.elapsed.as_millis(),
+                call.method,
+                call.url
+            );
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! Urls of Jenkins resources.
+//!
+//! `Url` is the controller url, which may include a context path (e.g.
+//! `https://ci.example.com/jenkins`), `Tree` a resource relative to it.
+//! Path segments are percent-encoded when the url is built, so job, node
+//! and view names are passed as they are.
+use std::fmt::Write as _;
+use urlencoding::encode;
+
+use crate::Result;
+
+/// Characters kept as they are in a path segment besides ascii alphanumerics
+/// (RFC 3986 `pchar`, without `;` which servlet containers strip as a path
+/// parameter).
+const SEGMENT_CHARS: &[u8] = b"-._~!$&'()*+,=:@";
+
+fn encode_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for b in segment.bytes() {
+        if b.is_ascii_alphanumeric() || SEGMENT_CHARS.contains(&b) {
+            encoded.push(char::from(b));
+        } else {
+            let _ = write!(encoded, "%{b:02X}");
+        }
+    }
+
+    encoded
+}
+
+#[derive(Clone)]
+pub struct Tree {
+    segments: Vec<String>,
+    query: String,
+}
+
+impl Tree {
+    /// A resource path with an optional query, e.g.
+    /// `computer/api/json?tree=computer[displayName]`. The query is taken
+    /// as it is, values in it must be encoded (see `param`).
+    pub fn new(query: String) -> Self {
+        let (path, query) = match query.split_once('?') {
+            Some((path, query)) => (path.to_string(), query.to_string()),
+            None => (query, String::new()),
+        };
+
+        Self {
+            segments: path
+                .split('/')
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect(),
+            query,
+        }
+    }
+
+    /// Prefix the resource with a job path: `team/service` becomes
+    /// `job/team/job/service`.
+    #[must_use]
+    pub fn build_path(mut self, path: &str) -> Self {
+        let jobs = path
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .flat_map(|name| ["job".to_string(), name.to_string()])
+            .collect::<Vec<_>>();
+        self.segments.splice(0..0, jobs);
+
+        self
+    }
+
+    /// Append a path segment, it may contain any character (an empty
+    /// segment adds a trailing slash).
+    #[must_use]
+    pub fn segment(mut self, segment: &str) -> Self {
+        self.segments.push(segment.to_string());
+        self
+    }
+
+    /// Append a relative resource path, with an optional query taken as it
+    /// is (see `new`).
+    #[must_use]
+    pub fn append(mut self, path: &str) -> Self {
+        let tree = Self::new(path.to_string());
+        self.segments.extend(tree.segments);
+        if !tree.query.is_empty() {
+            if !self.query.is_empty() {
+                self.query.push('&');
+            }
+            self.query.push_str(&tree.query);
+        }
+
+        self
+    }
+
+    /// Append a query parameter.
+    #[must_use]
+    pub fn param(mut self, key: &str, value: &str) -> Self {
+        if !self.query.is_empty() {
+            self.query.push('&');
+        }
+        let _ = write!(self.query, "{}={}", encode(key), encode(value));
+
+        self
+    }
+}
+
+impl std::fmt::Display for Tree {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let path = self
+            .segments
+            .iter()
+            .map(|s| encode_segment(s))
+            .collect::<Vec<_>>()
+            .join("/");
+
+        if self.query.is_empty() {
+            write!(f, "{path}")
+        } else {
+            write!(f, "{path}?{}", self.query)
+        }
+    }
+}
+
+/// The controller url without a trailing slash.
+#[derive(Clone)]
+pub struct Url {
+    base: String,
+}
+
+impl Url {
+    pub fn parse(url: &str) -> Result<Self> {
+        let uri = url
+            .parse::<hyper::Uri>()
+            .map_err(|e| format!("invalid url '{url}': {e}"))?;
+
+        let Some(scheme @ ("http" | "https")) = uri.scheme_str() else {
+            return Err(format!("invalid url '{url}': the scheme must be http or https").into());
+        };
+        let Some(authority) = uri.authority() else {
+            return Err(format!("invalid url '{url}': missing host").into());
+        };
+        if uri.query().is_some() {
+            return Err(format!("invalid url '{url}': unexpected query").into());
+        }
+
+        Ok(Self {
+            base: format!("{scheme}://{authority}{}", uri.path().trim_end_matches('/')),
+        })
+    }
+
+    /// Url of a resource, below the context path of the controller.
+    pub fn join(&self, tree: &Tree) -> Result<hyper::Uri> {
+        Ok(format!("{}/{tree}", self.base).parse::<hyper::Uri>()?)
+    }
+}
+
+impl std::fmt::Display for Url {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.base)
+    }
+}
@@ -0,0 +1,41 @@
+#![allow(clippy::struct_field_names)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Me {
+    pub id: String,
+    pub full_name: String,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct WhoAmI {
+    pub name: String,
+    pub anonymous: bool,
+    pub authenticated: bool,
+    pub authorities: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct NewToken {
+    pub status: String,
+    pub data: NewTokenData,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewTokenData {
+    pub token_name: String,
+    pub token_uuid: String,
+    pub token_value: String,
+}
+
+/// The token list is not exported by the json api, so it is read through the
+/// script console: one tab separated line per token with uuid, name, creation
+/// date, use counter and last use date (dates in milliseconds, 0 if unknown).
+pub const TOKEN_LIST_SCRIPT: &str = r"
+def property = User.current()?.getProperty(jenkins.security.ApiTokenProperty)
+property?.tokenList?.each { t ->
+    println([t.uuid, t.name, t.creationDate?.time ?: 0, t.useCounter ?: 0, t.lastUseDate?.time ?: 0].join('\t'))
+}
+";
@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+use crate::xml;
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct Info {
+    pub views: Vec<View>,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct View {
+    #[serde(rename = "_class")]
+    pub class: String,
+    pub name: String,
+    pub url: String,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct Jobs {
+    pub jobs: Vec<Job>,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct Job {
+    pub name: String,
+    pub color: Option<String>,
+}
+
+/// Config of a list view with an optional include regex.
+pub fn list_view_xml(name: &str, regex: &str, recurse: bool) -> String {
+    let regex = if regex.is_empty() {
+        String::new()
+    } else {
+        format!("<includeRegex>{}</includeRegex>", xml::escape(regex))
+    };
+
+    format!(
+        "<hudson.model.ListView>\
+         <name>{}</name>\
+         <filterExecutors>false</filterExecutors>\
+         <filterQueue>false</filterQueue>\
+         <properties class=\"hudson.model.View$PropertyList\"/>\
+         <jobNames><comparator class=\"hudson.util.CaseInsensitiveComparator\"/></jobNames>\
+         <jobFilters/>\
+         <columns>\
+         <hudson.views.StatusColumn/>\
+         <hudson.views.WeatherColumn/>\
+         <hudson.views.JobColumn/>\
+         <hudson.views.LastSuccessColumn/>\
+         <hudson.views.LastFailureColumn/>\
+         <hudson.views.LastDurationColumn/>\
+         <hudson.views.BuildButtonColumn/>\
+         </columns>\
+         {regex}\
+         <recurse>{recurse}</recurse>\
+         </hudson.model.ListView>",
+        xml::escape(name)
+    )
+}
+
+/// Config of a "my view" (jobs the current user has access to).
+pub fn my_view_xml(name: &str) -> String {
+    format!(
+        "<hudson.model.MyView>\
+         <name>{}</name>\
+         <filterExecutors>false</filterExecutors>\
+         <filterQueue>false</filterQueue>\
+         <properties class=\"hudson.model.View$PropertyList\"/>\
+         </hudson.model.MyView>",
+        xml::escape(name)
+    )
+}
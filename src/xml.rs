@@ -0,0 +1,57 @@
+//! Minimal helpers for the flat `config.xml` documents Jenkins serves.
+//! These are not a general purpose xml parser: they only look at the first
+//! element with a given tag.
+
+pub fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+pub fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Text of the first `<tag>` element, `None` if there is no such element.
+pub fn element_text(xml: &str, tag: &str) -> Option<String> {
+    if xml.contains(format!("<{tag}/>").as_str()) && !xml.contains(format!("<{tag}>").as_str()) {
+        return Some(String::new());
+    }
+
+    let open = format!("<{tag}>");
+    let start = xml.find(open.as_str())? + open.len();
+    let end = start + xml[start..].find(format!("</{tag}>").as_str())?;
+
+    Some(unescape(&xml[start..end]))
+}
+
+/// Replace the text of the first `<tag>` element. If there is no such element
+/// it is inserted right before the closing tag of the `root` element.
+pub fn set_element_text(xml: &str, root: &str, tag: &str, text: &str) -> Option<String> {
+    let element = format!("<{tag}>{}</{tag}>", escape(text));
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+
+    if let Some(start) = xml.find(open.as_str()) {
+        let end = start + xml[start..].find(close.as_str())? + close.len();
+        return Some(format!("{}{element}{}", &xml[..start], &xml[end..]));
+    }
+
+    let empty = format!("<{tag}/>");
+    if xml.contains(empty.as_str()) {
+        return Some(xml.replacen(empty.as_str(), element.as_str(), 1));
+    }
+
+    let root_close = xml.rfind(format!("</{root}>").as_str())?;
+    Some(format!(
+        "{}  {element}\n{}",
+        &xml[..root_close],
+        &xml[root_close..]
+    ))
+}
@@ -0,0 +1,306 @@
+mod common;
+
+use common::{MockJenkins, Reply};
+use serde_json::json;
+
+#[test]
+fn shutdown_and_restart() {
+    let mock = MockJenkins::start();
+
+    mock.run(&["shutdown", "on", "planned upgrade"])
+        .assert_success();
+    mock.run(&["shutdown", "on"]).assert_success();
+    mock.run(&["shutdown", "off"]).assert_success();
+    mock.run(&["restart"]).assert_success();
+    mock.run(&["restart", "--hard"]).assert_success();
+
+    assert_eq!(
+        mock.post_paths(),
+        [
+            "/quietDown?reason=planned%20upgrade",
+            "/quietDown",
+            "/cancelQuietDown",
+            "/safeRestart",
+            "/restart",
+        ]
+    );
+}
+
+#[test]
+fn copy_job_and_view() {
+    let mock = MockJenkins::start();
+
+    mock.run(&["copy", "job", "api", "api-copy"])
+        .assert_success();
+    mock.run(&["copy", "view", "ops", "ops copy"])
+        .assert_success();
+    let out = mock.run(&["copy", "job", "api", "team/api"]);
+    assert!(
+        out.stderr.contains("a directory is not enabled"),
+        "{}",
+        out.stderr
+    );
+
+    assert_eq!(
+        mock.post_paths(),
+        [
+            "/createItem?from=api&mode=copy&name=api-copy",
+            "/createView?from=ops&mode=copy&name=ops%20copy",
+        ]
+    );
+}
+
+#[test]
+fn run_script() {
+    let mock = MockJenkins::start();
+    mock.post("scriptText", Reply::chunks(&["Result: ", "42\n"]))
+        .post("computer/agent-1/scriptText", Reply::text("agent-1\n"));
+
+    let out = mock.run(&["script", "run", "-y", "-e", "println 6 * 7"]);
+    out.assert_success();
+    assert_eq!(out.stdout, "Result: 42\n");
+
+    std::fs::write(
+        mock.dir().join("host.groovy"),
+        "println 'hostname'.execute().text",
+    )
+    .unwrap();
+    let out = mock.run(&["script", "run", "-y", "--node", "agent-1", "host.groovy"]);
+    out.assert_success();
+    assert_eq!(out.stdout, "agent-1\n");
+
+    let posts = mock.posts();
+    assert_eq!(posts[0].path, "/scriptText");
+    assert_eq!(posts[0].body, "script=println%206%20%2A%207");
+    assert_eq!(posts[1].path, "/computer/agent-1/scriptText");
+    assert_eq!(
+        posts[1].body,
+        "script=println%20%27hostname%27.execute%28%29.text"
+    );
+}
+
+#[test]
+fn run_script_needs_confirmation() {
+    let mock = MockJenkins::start();
+
+    let out = mock.run_with_input(
+        &["script", "run", "-e", "Jenkins.instance.restart()"],
+        "n\n",
+    );
+    out.assert_success();
+    assert!(out
+        .stdout
+        .contains("Run the script on the controller? [y/N]"));
+    assert!(out.stderr.contains("script execution aborted"));
+
+    let out = mock.run_with_input(&["script", "run", "-"], "println 1");
+    out.assert_failure();
+    assert!(out
+        .stderr
+        .contains("reading the script from stdin requires --yes"));
+
+    assert!(mock.posts().is_empty());
+}
+
+#[test]
+fn run_script_error() {
+    let mock = MockJenkins::start();
+    mock.post("scriptText", Reply::status(500));
+
+    let out = mock.run(&["script", "run", "-y", "-e", "boom"]);
+    out.assert_failure();
+    assert!(out.stderr.contains("script console returned 500"));
+}
+
+fn me(mock: &MockJenkins) {
+    mock.get(
+        "me/api/json",
+        Reply::json(&json!({ "id": "admin", "fullName": "Jenkins Admin" })),
+    );
+}
+
+#[test]
+fn whoami() {
+    let mock = MockJenkins::start();
+    me(&mock);
+    mock.get(
+        "whoAmI/api/json",
+        Reply::json(&json!({
+            "name": "admin",
+            "anonymous": false,
+            "authenticated": true,
+            "authorities": ["authenticated", "admins"],
+        })),
+    );
+
+    let out = mock.run(&["whoami"]);
+    out.assert_success();
+    assert_eq!(
+        out.stdout,
+        format!(
+            "{:.<20}admin\n{:.<20}Jenkins Admin\n{:.<20}valid\n{:.<20}authenticated, admins\n",
+            "user", "full name", "token", "authorities"
+        )
+    );
+    assert_eq!(mock.requests()[0].path, "/me/api/json?tree=id,fullName");
+}
+
+#[test]
+fn whoami_with_an_invalid_token() {
+    let mock = MockJenkins::start();
+    mock.get("me/api/json", Reply::status(401));
+
+    let out = mock.run(&["whoami"]);
+    out.assert_failure();
+    assert_eq!(out.stdout, format!("{:.<20}invalid\n", "token"));
+    assert!(out.stderr.contains("authentication failed (401)"));
+}
+
+#[test]
+fn info_prints_the_url() {
+    let mock = MockJenkins::start();
+
+    let out = mock.run(&["info"]);
+    out.assert_success();
+    assert_eq!(out.stdout, format!("{}\n", mock.url()));
+    assert!(mock.requests().is_empty());
+}
+
+#[test]
+fn create_token_and_save_it() {
+    let mock = MockJenkins::start();
+    mock.post(
+        "me/descriptorByName/jenkins.security.ApiTokenProperty/generateNewToken",
+        Reply::json(&json!({
+            "status": "ok",
+            "data": { "tokenName": "ci", "tokenUuid": "9c1f", "tokenValue": "11ffee" },
+        })),
+    );
+
+    let out = mock.run(&["token", "create", "ci", "--save"]);
+    out.assert_success();
+    assert_eq!(
+        out.stdout,
+        format!(
+            "{:.<20}ci\n{:.<20}9c1f\n{:.<20}11ffee\n",
+            "name", "uuid", "token"
+        )
+    );
+    assert_eq!(mock.posts()[0].body, "newTokenName=ci");
+
+    let config = std::fs::read_to_string(mock.dir().join("config.toml")).unwrap();
+    let config: toml::Value = toml::from_str(&config).unwrap();
+    assert_eq!(config["current_context"].as_str(), Some("default"));
+    let context = &config["contexts"]["default"];
+    assert_eq!(context["url"].as_str(), Some(mock.url()));
+    assert_eq!(context["user"].as_str(), Some(common::USER));
+    assert_eq!(context["token"].as_str(), Some("11ffee"));
+}
+
+#[test]
+fn save_a_token_into_an_existing_config() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mock = MockJenkins::start();
+    mock.post(
+        "me/descriptorByName/jenkins.security.ApiTokenProperty/generateNewToken",
+        Reply::json(&json!({
+            "status": "ok",
+            "data": { "tokenName": "ci", "tokenUuid": "9c1f", "tokenValue": "11ffee" },
+        })),
+    );
+    let path = mock.dir().join("config.toml");
+    std::fs::write(&path, "[contexts.prod]\nretries = 1\n").unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+    let out = mock.run(&["--context", "prdo", "token", "create", "ci", "--save"]);
+    out.assert_failure();
+    assert!(
+        out.stderr
+            .contains("unknown context prdo, known contexts: prod"),
+        "{}",
+        out.stderr
+    );
+    assert!(mock.requests().is_empty());
+
+    mock.run(&["--context", "prod", "token", "create", "ci", "--save"])
+        .assert_success();
+    let config = std::fs::read_to_string(&path).unwrap();
+    let config: toml::Value = toml::from_str(&config).unwrap();
+    assert_eq!(config["contexts"]["prod"]["token"].as_str(), Some("11ffee"));
+    assert!(config["contexts"].get("prdo").is_none());
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+}
+
+#[test]
+fn list_tokens() {
+    let mock = MockJenkins::start();
+    mock.post(
+        "scriptText",
+        Reply::text("9c1f\tci\t0\t3\t0\n2b7a\tlaptop\t0\t0\t0\n"),
+    );
+
+    let out = mock.run(&["token", "list"]);
+    out.assert_success();
+    let lines = out.stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(
+        lines[0],
+        format!("{:<38}{:<30}{:<16}{:<8}never", "9c1f", "ci", "never", "3")
+    );
+    assert!(mock.posts()[0].body.starts_with("script="));
+}
+
+#[test]
+fn list_tokens_needs_administer() {
+    let mock = MockJenkins::start();
+    mock.post("scriptText", Reply::status(403).body("Forbidden"));
+
+    let out = mock.run(&["token", "list"]);
+    out.assert_failure();
+    assert!(
+        out.stderr.contains(
+            "token list runs a script in the script console, which requires \
+             the Overall/Administer permission (403)"
+        ),
+        "{}",
+        out.stderr
+    );
+}
+
+#[test]
+fn revoke_token() {
+    let mock = MockJenkins::start();
+    mock.post(
+        "me/descriptorByName/jenkins.security.ApiTokenProperty/revoke",
+        Reply::ok(),
+    );
+
+    let out = mock.run(&["token", "revoke", "9c1f"]);
+    assert!(out.stderr.contains("token revocation aborted"));
+    mock.run(&["token", "revoke", "-y", "9c1f"])
+        .assert_success();
+
+    assert_eq!(
+        mock.post_paths(),
+        ["/me/descriptorByName/jenkins.security.ApiTokenProperty/revoke?tokenUuid=9c1f"]
+    );
+}
+
+#[test]
+fn revoke_unknown_token() {
+    let mock = MockJenkins::start();
+    mock.post(
+        "me/descriptorByName/jenkins.security.ApiTokenProperty/revoke",
+        Reply::status(500),
+    );
+
+    let out = mock.run(&["token", "revoke", "-y", "0000"]);
+    out.assert_failure();
+    assert!(
+        out.stderr.contains("revoke the token 0000"),
+        "{}",
+        out.stderr
+    );
+}
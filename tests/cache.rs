@@ -0,0 +1,227 @@
+mod common;
+
+use common::{computer, computers, job, MockJenkins, Reply};
+use serde_json::json;
+
+const FOLDER: &str = "com.cloudbees.hudson.plugins.folder.Folder";
+const PIPELINE: &str = "org.jenkinsci.plugins.workflow.job.WorkflowJob";
+
+fn jobs(mock: &MockJenkins) {
+    mock.get(
+        "api/json",
+        Reply::json(&json!({ "jobs": [job(FOLDER, "team"), job(PIPELINE, "nightly")] })),
+    )
+    .get(
+        "job/team/api/json",
+        Reply::json(&json!({
+            "jobs": [job(PIPELINE, "team/api"), job(PIPELINE, "team/web")],
+        })),
+    );
+}
+
+#[test]
+fn list_jobs_from_the_cache() {
+    let mock = MockJenkins::start();
+    jobs(&mock);
+
+    // Without a TTL nothing is cached
+    mock.run(&["job", "list"]).assert_success();
+    mock.run(&["job", "list"]).assert_success();
+    assert_eq!(mock.requests().len(), 4);
+
+    let out = mock.run(&["--cache-ttl", "10m", "job", "list"]);
+    out.assert_success();
+    assert_eq!(out.stdout, "team => api\nteam => web\nnightly\n");
+    assert_eq!(mock.requests().len(), 6);
+
+    let cached = mock.run(&["--cache-ttl", "10m", "job", "list"]);
+    cached.assert_success();
+    assert_eq!(cached.stdout, out.stdout);
+    assert_eq!(mock.requests().len(), 6);
+
+    mock.run(&["--cache-ttl", "10m", "--refresh", "job", "list"])
+        .assert_success();
+    assert_eq!(mock.requests().len(), 8);
+
+    std::fs::write(
+        mock.dir().join("config.toml"),
+        "[contexts.default]\ncache_ttl = \"0s\"\n",
+    )
+    .unwrap();
+    mock.run(&["job", "list"]).assert_success();
+    assert_eq!(mock.requests().len(), 10);
+}
+
+#[test]
+fn list_nodes_from_the_cache() {
+    let mock = MockJenkins::start();
+    mock.get(
+        "computer/api/json",
+        Reply::json(&computers(&[
+            computer("built-in", &[], false),
+            computer("agent-1", &["linux"], true),
+        ])),
+    );
+    std::fs::write(
+        mock.dir().join("config.toml"),
+        "[contexts.default]\ncache_ttl = \"10m\"\n",
+    )
+    .unwrap();
+
+    let out = mock.run(&["node", "list", "--selector", "linux"]);
+    out.assert_success();
+    assert_eq!(out.stdout, "agent-1\n");
+    let cached = mock.run(&["node", "list", "--selector", "linux"]);
+    cached.assert_success();
+    assert_eq!(cached.stdout, out.stdout);
+    assert_eq!(mock.requests().len(), 1);
+
+    // The state of the nodes is always fetched
+    mock.run(&["node", "list", "--status"]).assert_success();
+    mock.run(&["node", "health"]).assert_success();
+    assert_eq!(mock.requests().len(), 3);
+
+    // Changing a node drops the cached list
+    mock.get(
+        "computer/agent-1/api/json",
+        Reply::json(&computer("agent-1", &["linux"], true)),
+    )
+    .post("computer/agent-1/toggleOffline", Reply::ok());
+    mock.run(&["node", "set", "agent-1", "online"])
+        .assert_success();
+    mock.run(&["node", "list"]).assert_success();
+    assert_eq!(mock.requests().last().unwrap().path, "/computer/api/json");
+
+    let config = "<slave><label>linux</label></slave>";
+    mock.get("computer/agent-1/config.xml", Reply::text(config))
+        .post("computer/agent-1/config.xml", Reply::ok());
+    for args in [
+        &["node", "labels", "add", "agent-1", "docker"][..],
+        &["node", "config", "apply", "agent-1", "-"],
+    ] {
+        mock.run(&["node", "list"]).assert_success();
+        mock.run_with_input(args, config).assert_success();
+        let count = mock.requests().len();
+        mock.run(&["node", "list"]).assert_success();
+        assert_eq!(mock.requests().len(), count + 1);
+        assert_eq!(mock.requests().last().unwrap().path, "/computer/api/json");
+    }
+}
+
+#[test]
+fn suggest_similar_jobs() {
+    let mock = MockJenkins::start();
+    jobs(&mock);
+
+    let out = mock.run(&["job", "list", "team/apl"]);
+    out.assert_failure();
+    assert!(
+        out.stderr
+            .contains("job team/apl not found, did you mean team/api?"),
+        "{}",
+        out.stderr
+    );
+
+    // Without a cache only the parent folder is looked at
+    let count = mock.requests().len();
+    let out = mock.run(&["job", "build", "web"]);
+    out.assert_failure();
+    assert!(out.stderr.contains("job web not found"), "{}", out.stderr);
+    assert!(!out.stderr.contains("did you mean"));
+    assert!(mock.requests()[count..]
+        .iter()
+        .all(|r| !r.path.starts_with("/job/team/api/json")));
+
+    // The whole tree once it is cached
+    mock.run(&["--cache-ttl", "10m", "job", "list"])
+        .assert_success();
+    let out = mock.run(&["--cache-ttl", "10m", "job", "build", "web"]);
+    out.assert_failure();
+    assert!(
+        out.stderr.contains("did you mean team/web?"),
+        "{}",
+        out.stderr
+    );
+
+    let out = mock.run(&["--cache-ttl", "10m", "job", "build", "release"]);
+    out.assert_failure();
+    assert!(
+        out.stderr.contains("job release not found"),
+        "{}",
+        out.stderr
+    );
+    assert!(!out.stderr.contains("did you mean"));
+
+    // The job exists, only the build is missing
+    mock.get(
+        "job/nightly/api/json",
+        Reply::json(&json!({ "name": "nightly" })),
+    );
+    let out = mock.run(&["job", "download", "nightly", "7", "log"]);
+    out.assert_failure();
+    assert!(!out.stderr.contains("not found"), "{}", out.stderr);
+}
+
+#[test]
+fn one_cache_per_context() {
+    let mock = MockJenkins::start();
+    jobs(&mock);
+    std::fs::write(
+        mock.dir().join("config.toml"),
+        "[contexts.a]\ncache_ttl = \"10m\"\n\n[contexts.b]\ncache_ttl = \"10m\"\n",
+    )
+    .unwrap();
+
+    mock.run(&["--context", "a", "job", "list"])
+        .assert_success();
+    mock.run(&["--context", "a", "job", "list"])
+        .assert_success();
+    assert_eq!(mock.requests().len(), 2);
+
+    // Same url and user, another context
+    mock.run(&["--context", "b", "job", "list"])
+        .assert_success();
+    assert_eq!(mock.requests().len(), 4);
+    mock.run(&["--context", "a", "job", "list"])
+        .assert_success();
+    assert_eq!(mock.requests().len(), 4);
+
+    assert!(mock.dir().join(".cache/jenkinsctl/context/a").is_dir());
+    assert!(mock.dir().join(".cache/jenkinsctl/context/b").is_dir());
+}
+
+#[test]
+fn deleted_jobs_are_not_suggested() {
+    let mock = MockJenkins::start();
+    mock.get(
+        "api/json",
+        Reply::json(&json!({ "jobs": [job(FOLDER, "team"), job(PIPELINE, "nightly")] })),
+    )
+    .get(
+        "job/team/api/json",
+        Reply::json(&json!({
+            "jobs": [job(PIPELINE, "team/api"), job(PIPELINE, "team/web")],
+        })),
+    )
+    // team/api is deleted once cached
+    .get(
+        "job/team/api/json",
+        Reply::json(&json!({ "jobs": [job(PIPELINE, "team/web")] })),
+    );
+
+    mock.run(&["--cache-ttl", "10m", "job", "list"])
+        .assert_success();
+    let out = mock.run(&["--cache-ttl", "10m", "job", "build", "team/api"]);
+    out.assert_failure();
+    assert!(
+        out.stderr.contains("job team/api not found"),
+        "{}",
+        out.stderr
+    );
+    assert!(!out.stderr.contains("did you mean"), "{}", out.stderr);
+
+    // The cache was refreshed on the way
+    let out = mock.run(&["--cache-ttl", "10m", "job", "list"]);
+    out.assert_success();
+    assert_eq!(out.stdout, "team => web\nnightly\n");
+}
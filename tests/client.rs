@@ -0,0 +1,191 @@
+mod common;
+
+use common::{computer, computers, job, queue_item, MockJenkins, Reply, TOKEN, USER};
+use futures::TryStreamExt;
+use jenkinsctl::{auth, jenkins::ClientOptions, JenkinsClient};
+use serde_json::json;
+
+fn client(mock: &MockJenkins) -> JenkinsClient {
+    let credentials = auth::Plain {
+        user: USER.to_string(),
+        token: TOKEN.to_string(),
+    };
+
+    JenkinsClient::new(mock.url(), &credentials, ClientOptions::default()).unwrap()
+}
+
+#[tokio::test]
+async fn jobs_are_the_leaves_of_the_folder_tree() {
+    let mock = MockJenkins::start();
+    mock.get(
+        "api/json",
+        Reply::json(&json!({ "jobs": [
+            job("com.cloudbees.hudson.plugins.folder.Folder", "a"),
+            job("hudson.model.FreeStyleProject", "top"),
+        ] })),
+    )
+    .get(
+        "job/a/api/json",
+        Reply::json(&json!({ "jobs": [
+            job("com.cloudbees.hudson.plugins.folder.Folder", "a/b"),
+            job("com.cloudbees.hudson.plugins.folder.Folder", "a/empty"),
+        ] })),
+    )
+    .get(
+        "job/a/job/b/api/json",
+        Reply::json(
+            &json!({ "jobs": [job("org.jenkinsci.plugins.workflow.job.WorkflowJob", "a/b/c")] }),
+        ),
+    )
+    .get(
+        "job/a/job/empty/api/json",
+        Reply::json(&json!({ "jobs": [] })),
+    );
+
+    let jobs = client(&mock).jobs().await.unwrap();
+    let names = jobs
+        .iter()
+        .map(|j| j.full_name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["a/b/c", "top"]);
+}
+
+#[tokio::test]
+async fn missing_folder_fails_the_walk() {
+    let mock = MockJenkins::start();
+    mock.get(
+        "api/json",
+        Reply::json(
+            &json!({ "jobs": [job("com.cloudbees.hudson.plugins.folder.Folder", "gone")] }),
+        ),
+    );
+
+    let error = client(&mock).jobs().await.unwrap_err();
+    assert_eq!(error.to_string(), "404");
+}
+
+#[tokio::test]
+async fn nodes() {
+    let mock = MockJenkins::start();
+    mock.get(
+        "computer/api/json",
+        Reply::json(&computers(&[
+            computer("built-in", &[], false),
+            computer("agent-1", &["linux"], true),
+        ])),
+    );
+
+    let nodes = client(&mock).nodes().await.unwrap();
+    assert_eq!(nodes.len(), 2);
+    assert_eq!(nodes[1].display_name, "agent-1");
+    assert!(nodes[1].offline);
+    assert_eq!(nodes[1].labels(), ["linux", "agent-1"]);
+}
+
+#[tokio::test]
+async fn trigger_and_wait_for_the_build() {
+    let mock = MockJenkins::start();
+    let mut started = queue_item(21, "http://ci/job/api/");
+    started["executable"] = json!({ "number": 9, "url": "http://ci/job/api/9/" });
+    mock.post(
+        "job/api/buildWithParameters",
+        Reply::status(201).header("Location", &format!("{}/queue/item/21/", mock.url())),
+    )
+    .get(
+        "queue/item/21/api/json",
+        Reply::json(&queue_item(21, "http://ci/job/api/")),
+    )
+    .get("queue/item/21/api/json", Reply::json(&started));
+
+    let client = client(&mock);
+    let params = [("env".to_string(), "prod".to_string())];
+    let item = client.trigger("api", Some(&params)).await.unwrap();
+    assert_eq!(item.id, 21);
+    assert!(item.executable.is_none());
+
+    assert_eq!(client.wait_for_build(item.id).await.unwrap(), 9);
+    assert_eq!(
+        mock.post_paths(),
+        ["/job/api/buildWithParameters?delay=0sec&env=prod"]
+    );
+}
+
+#[tokio::test]
+async fn trigger_without_a_queue_item() {
+    let mock = MockJenkins::start();
+    mock.post("job/api/build", Reply::status(201));
+
+    let error = client(&mock).trigger("api", None).await.unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "no queue item returned for the build of api"
+    );
+}
+
+#[tokio::test]
+async fn wait_for_a_cancelled_item() {
+    let mock = MockJenkins::start();
+    let mut cancelled = queue_item(22, "http://ci/job/api/");
+    cancelled["cancelled"] = json!(true);
+    mock.get("queue/item/22/api/json", Reply::json(&cancelled));
+
+    let error = client(&mock).wait_for_build(22).await.unwrap_err();
+    assert_eq!(error.to_string(), "queue item 22 was cancelled");
+}
+
+#[tokio::test]
+async fn builds() {
+    let mock = MockJenkins::start();
+    mock.get(
+        "job/a/job/b/api/json",
+        Reply::json(&json!({
+            "_class": "org.jenkinsci.plugins.workflow.job.WorkflowJob",
+            "builds": [{ "_class": "org.jenkinsci.plugins.workflow.job.WorkflowRun", "number": 2, "url": "http://ci/job/a/job/b/2/" }],
+            "nextBuildNumber": 3,
+        })),
+    );
+
+    let builds = client(&mock).builds("a/b").await.unwrap();
+    assert_eq!(builds.len(), 1);
+    assert_eq!(builds[0].number, 2);
+    assert_eq!(builds[0].url, "http://ci/job/a/job/b/2/");
+}
+
+#[tokio::test]
+async fn console_streams_until_the_build_is_done() {
+    let mock = MockJenkins::start();
+    mock.get(
+        "job/api/9/logText/progressiveText?start=0",
+        Reply::chunks(&["one\n", "two\n"])
+            .header("X-Text-Size", "8")
+            .header("X-More-Data", "true"),
+    )
+    .get(
+        "job/api/9/logText/progressiveText?start=8",
+        Reply::chunks(&["three\n"]).header("X-Text-Size", "14"),
+    );
+
+    let client = client(&mock);
+    let chunks = client
+        .console("api", 9)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(chunks, ["one\ntwo\n", "three\n"]);
+}
+
+#[tokio::test]
+async fn console_of_a_missing_build() {
+    let mock = MockJenkins::start();
+
+    let client = client(&mock);
+    let error = client
+        .console("api", 404)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "failed to get the console of api #404: 404 Not Found"
+    );
+}
@@ -0,0 +1,463 @@
+//! In-process mock Jenkins for the integration tests.
+//!
+//! Routes are keyed by method and path, a route may also include the query
+//! (`job/a/5/api/json?tree=actions`) to tell requests to the same path apart.
+//! A route replies with its replies in turn, the last one is repeated. Every
+//! request is recorded and unknown routes get a 404.
+#![allow(dead_code)]
+use bytes::Bytes;
+use futures::StreamExt as _;
+use http_body_util::{combinators::BoxBody, BodyExt, Full, StreamBody};
+use hyper::{body::Frame, service::service_fn, StatusCode};
+use hyper_util::rt::TokioIo;
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{Arc, Mutex},
+};
+
+pub const USER: &str = "admin";
+pub const TOKEN: &str = "11aa22bb33cc";
+
+#[derive(Clone)]
+pub struct Reply {
+    status: u16,
+    headers: Vec<(String, String)>,
+    chunks: Vec<Bytes>,
+    chunked: bool,
+    stalled: bool,
+}
+
+impl Reply {
+    pub fn status(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            chunks: Vec::new(),
+            chunked: false,
+            stalled: false,
+        }
+    }
+
+    pub fn ok() -> Self {
+        Self::status(200)
+    }
+
+    pub fn json(value: &serde_json::Value) -> Self {
+        Self::ok()
+            .header("Content-Type", "application/json")
+            .body(&value.to_string())
+    }
+
+    pub fn text(text: &str) -> Self {
+        Self::ok().header("Content-Type", "text/plain").body(text)
+    }
+
+    pub fn bytes(data: &[u8]) -> Self {
+        let mut reply = Self::ok();
+        reply.chunks = vec![Bytes::copy_from_slice(data)];
+        reply
+    }
+
+    /// A body sent with chunked transfer encoding, one chunk per item.
+    pub fn chunks(chunks: &[&str]) -> Self {
+        let mut reply = Self::ok();
+        reply.chunks = chunks
+            .iter()
+            .map(|c| Bytes::copy_from_slice(c.as_bytes()))
+            .collect();
+        reply.chunked = true;
+        reply
+    }
+
+    /// Never end the body after its chunks, as a controller stuck mid-way.
+    pub fn stall(mut self) -> Self {
+        self.chunked = true;
+        self.stalled = true;
+        self
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn body(mut self, body: &str) -> Self {
+        self.chunks = vec![Bytes::copy_from_slice(body.as_bytes())];
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Request {
+    pub method: String,
+    /// Path and query as sent, e.g. `/job/a/build?delay=0sec`.
+    pub path: String,
+    /// Request target as sent, absolute when the mock acts as a proxy.
+    pub target: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    /// When the request was received.
+    pub at: std::time::Instant,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+#[derive(Default)]
+struct State {
+    routes: HashMap<(String, String), Vec<Reply>>,
+    requests: Vec<Request>,
+}
+
+pub struct MockJenkins {
+    url: String,
+    dir: PathBuf,
+    state: Arc<Mutex<State>>,
+}
+
+impl MockJenkins {
+    /// Serve Jenkins at the root of a random local port.
+    pub fn start() -> Self {
+        Self::with_prefix("")
+    }
+
+    /// Serve Jenkins below a context path (e.g. `/jenkins`).
+    pub fn with_prefix(prefix: &str) -> Self {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let server = state.clone();
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            runtime.block_on(serve(listener, server));
+        });
+
+        let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("mock-{}", addr.port()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        Self {
+            url: format!("http://{addr}{prefix}"),
+            dir,
+            state,
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Scratch directory of the test, the working directory and `HOME` of
+    /// the commands.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Add a reply to a route, `path` is relative to the Jenkins url.
+    pub fn on(&self, method: &str, path: &str, reply: Reply) -> &Self {
+        let prefix = self.url.splitn(4, '/').nth(3).unwrap_or_default();
+        let path = if prefix.is_empty() {
+            format!("/{path}")
+        } else {
+            format!("/{prefix}/{path}")
+        };
+
+        self.state
+            .lock()
+            .unwrap()
+            .routes
+            .entry((method.to_string(), path))
+            .or_default()
+            .push(reply);
+        self
+    }
+
+    pub fn get(&self, path: &str, reply: Reply) -> &Self {
+        self.on("GET", path, reply)
+    }
+
+    pub fn post(&self, path: &str, reply: Reply) -> &Self {
+        self.on("POST", path, reply)
+    }
+
+    pub fn requests(&self) -> Vec<Request> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Recorded requests other than GETs.
+    pub fn posts(&self) -> Vec<Request> {
+        self.requests()
+            .into_iter()
+            .filter(|r| r.method != "GET")
+            .collect()
+    }
+
+    /// Paths of the recorded requests other than GETs.
+    pub fn post_paths(&self) -> Vec<String> {
+        self.posts().into_iter().map(|r| r.path).collect()
+    }
+
+    /// `jenkinsctl` pointed at the mock, authenticated with a user and token.
+    pub fn command(&self) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_jenkinsctl"));
+        command
+            .args(["--url", &self.url, "--user", USER, "--token", TOKEN])
+            .current_dir(&self.dir)
+            .env("HOME", &self.dir)
+            .env("JENKINSCTL_CONFIG", self.dir.join("config.toml"))
+            .env("NO_COLOR", "1")
+            .env("RUST_LOG", "info")
+            .env_remove("XDG_CONFIG_HOME")
+            .env_remove("XDG_CACHE_HOME")
+            .env_remove("NETRC");
+        for name in [
+            "JENKINS_URL",
+            "JENKINS_USER",
+            "JENKINS_TOKEN",
+            "HTTP_PROXY",
+            "HTTPS_PROXY",
+            "NO_PROXY",
+        ] {
+            command.env_remove(name).env_remove(name.to_lowercase());
+        }
+
+        command
+    }
+
+    /// Run `jenkinsctl` with the arguments.
+    pub fn run(&self, args: &[&str]) -> Output {
+        Output::of(self.command().args(args), None)
+    }
+
+    /// Run `jenkinsctl` with the arguments and data on stdin.
+    pub fn run_with_input(&self, args: &[&str], input: &str) -> Output {
+        Output::of(self.command().args(args), Some(input))
+    }
+}
+
+pub struct Output {
+    pub success: bool,
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl Output {
+    pub fn of(command: &mut Command, input: Option<&str>) -> Self {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        // Dropping stdin closes it, so prompts read an empty answer
+        let mut stdin = child.stdin.take().unwrap();
+        if let Some(input) = input {
+            stdin.write_all(input.as_bytes()).unwrap();
+        }
+        drop(stdin);
+
+        let output = child.wait_with_output().unwrap();
+        Self {
+            success: output.status.success(),
+            code: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        }
+    }
+
+    /// Panic with the captured output unless the command succeeded.
+    pub fn assert_success(&self) -> &Self {
+        assert!(
+            self.success,
+            "command failed ({:?})\nstdout:\n{}\nstderr:\n{}",
+            self.code, self.stdout, self.stderr
+        );
+        self
+    }
+
+    pub fn assert_failure(&self) -> &Self {
+        assert!(
+            !self.success,
+            "command succeeded\nstdout:\n{}\nstderr:\n{}",
+            self.stdout, self.stderr
+        );
+        self
+    }
+}
+
+async fn serve(listener: std::net::TcpListener, state: Arc<Mutex<State>>) {
+    let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let state = state.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |req| respond(state.clone(), req));
+            let _ = hyper::server::conn::http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await;
+        });
+    }
+}
+
+async fn respond(
+    state: Arc<Mutex<State>>,
+    req: hyper::Request<hyper::body::Incoming>,
+) -> Result<hyper::Response<BoxBody<Bytes, Infallible>>, Infallible> {
+    let at = std::time::Instant::now();
+    let method = req.method().to_string();
+    let target = req.uri().to_string();
+    let path = req
+        .uri()
+        .path_and_query()
+        .map_or_else(|| req.uri().path().to_string(), ToString::to_string);
+    let headers = req
+        .headers()
+        .iter()
+        .map(|(n, v)| (n.to_string(), String::from_utf8_lossy(v.as_bytes()).into()))
+        .collect();
+    let uri_path = req.uri().path().to_string();
+    let body = req
+        .into_body()
+        .collect()
+        .await
+        .map(|b| String::from_utf8_lossy(&b.to_bytes()).into_owned())
+        .unwrap_or_default();
+
+    let reply = {
+        let mut state = state.lock().unwrap();
+        state.requests.push(Request {
+            method: method.clone(),
+            path: path.clone(),
+            target,
+            headers,
+            body,
+            at,
+        });
+
+        let replies = match state.routes.get_mut(&(method.clone(), path)) {
+            Some(replies) => Some(replies),
+            None => state.routes.get_mut(&(method, uri_path)),
+        };
+        match replies {
+            Some(replies) if replies.len() > 1 => replies.remove(0),
+            Some(replies) => replies[0].clone(),
+            None => Reply::status(404).body("Not Found"),
+        }
+    };
+
+    let mut res = hyper::Response::builder().status(StatusCode::from_u16(reply.status).unwrap());
+    for (name, value) in &reply.headers {
+        res = res.header(name, value);
+    }
+    let body = if reply.chunked {
+        let frames = futures::stream::iter(
+            reply
+                .chunks
+                .into_iter()
+                .map(|c| Ok::<_, Infallible>(Frame::data(c))),
+        );
+        if reply.stalled {
+            BoxBody::new(StreamBody::new(frames.chain(futures::stream::pending())))
+        } else {
+            BoxBody::new(StreamBody::new(frames))
+        }
+    } else {
+        BoxBody::new(Full::new(reply.chunks.concat().into()))
+    };
+
+    Ok(res.body(body).unwrap())
+}
+
+/// A node as served by `computer/<NAME>/api/json`.
+pub fn computer(name: &str, labels: &[&str], offline: bool) -> serde_json::Value {
+    let mut labels = labels
+        .iter()
+        .map(|l| serde_json::json!({ "name": l }))
+        .collect::<Vec<_>>();
+    labels.push(serde_json::json!({ "name": name }));
+
+    serde_json::json!({
+        "_class": "hudson.slaves.SlaveComputer",
+        "assignedLabels": labels,
+        "description": "",
+        "displayName": name,
+        "icon": "symbol-computer",
+        "iconClassName": "symbol-computer",
+        "idle": true,
+        "jnlpAgent": true,
+        "launchSupported": false,
+        "manualLaunchAllowed": true,
+        "monitorData": {
+            "hudson.node_monitors.DiskSpaceMonitor": {
+                "timestamp": 1_700_000_000_000_u64,
+                "path": "/var/lib/jenkins",
+                "size": 50_u64 << 30,
+            },
+            "hudson.node_monitors.ResponseTimeMonitor": {
+                "timestamp": 1_700_000_000_000_u64,
+                "average": 42,
+            },
+            "hudson.node_monitors.ClockMonitor": { "diff": -3 },
+            "hudson.node_monitors.ArchitectureMonitor": "Linux (amd64)",
+        },
+        "numExecutors": 2,
+        "offline": offline,
+        "offlineCause": null,
+        "offlineCauseReason": "",
+        "temporarilyOffline": false,
+    })
+}
+
+/// `computer/api/json` with the nodes.
+pub fn computers(nodes: &[serde_json::Value]) -> serde_json::Value {
+    serde_json::json!({
+        "_class": "hudson.model.ComputerSet",
+        "busyExecutors": 1,
+        "computer": nodes,
+        "displayName": "Nodes",
+        "totalExecutors": 4,
+    })
+}
+
+/// A job or folder entry of `api/json?tree=jobs[...]`.
+pub fn job(class: &str, full_name: &str) -> serde_json::Value {
+    let name = full_name.rsplit('/').next().unwrap();
+    serde_json::json!({
+        "_class": class,
+        "fullDisplayName": full_name.replace('/', " » "),
+        "fullName": full_name,
+        "name": name,
+    })
+}
+
+/// A queue item of `queue/api/json` or `queue/item/<ID>/api/json`.
+pub fn queue_item(id: u64, job_url: &str) -> serde_json::Value {
+    serde_json::json!({
+        "_class": "hudson.model.Queue$WaitingItem",
+        "id": id,
+        "why": "Waiting for next available executor",
+        "inQueueSince": 1_700_000_000_000_u64,
+        "stuck": false,
+        "blocked": false,
+        "buildable": true,
+        "task": { "name": job_url.trim_end_matches('/').rsplit('/').next().unwrap(), "url": job_url },
+    })
+}
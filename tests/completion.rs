@@ -0,0 +1,154 @@
+mod common;
+
+use common::{computer, computers, job, MockJenkins, Output, Reply};
+use serde_json::json;
+
+const FOLDER: &str = "com.cloudbees.hudson.plugins.folder.Folder";
+const MULTIBRANCH: &str = "org.jenkinsci.plugins.workflow.multibranch.WorkflowMultiBranchProject";
+const PIPELINE: &str = "org.jenkinsci.plugins.workflow.job.WorkflowJob";
+
+/// Complete the last of the words, as the shell wrappers do.
+fn complete(mock: &MockJenkins, words: &[&str]) -> Output {
+    let index = (words.len() - 1).to_string();
+    let mut args = vec!["complete-names", &index, "jenkinsctl"];
+    args.extend_from_slice(&words[1..]);
+
+    mock.run(&args)
+}
+
+fn jobs(mock: &MockJenkins) {
+    mock.get(
+        "api/json",
+        Reply::json(&json!({ "jobs": [job(FOLDER, "team"), job(PIPELINE, "nightly")] })),
+    )
+    .get(
+        "job/team/api/json",
+        Reply::json(&json!({
+            "jobs": [
+                job(MULTIBRANCH, "team/service"),
+                job(PIPELINE, "team/server-sync"),
+                job(PIPELINE, "team/web"),
+            ],
+        })),
+    );
+}
+
+#[test]
+fn complete_job_paths() {
+    let mock = MockJenkins::start();
+    jobs(&mock);
+
+    let out = complete(&mock, &["jenkinsctl", "job", "build", ""]);
+    out.assert_success();
+    assert_eq!(out.stdout, "team/\nnightly\n");
+
+    let out = complete(&mock, &["jenkinsctl", "job", "build", "team/ser"]);
+    out.assert_success();
+    assert_eq!(out.stdout, "team/service/\nteam/server-sync\n");
+    assert_eq!(
+        mock.requests().last().unwrap().path,
+        "/job/team/api/json?tree=jobs[name]"
+    );
+
+    let out = complete(
+        &mock,
+        &["jenkinsctl", "-v", "job", "kill", "-s", "KILL", "t"],
+    );
+    out.assert_success();
+    assert_eq!(out.stdout, "team/\n");
+
+    let out = complete(&mock, &["jenkinsctl", "queue", "cancel", "--job", "team/w"]);
+    out.assert_success();
+    assert_eq!(out.stdout, "team/web\n");
+}
+
+#[test]
+fn complete_from_the_cache() {
+    let mock = MockJenkins::start();
+    jobs(&mock);
+
+    complete(&mock, &["jenkinsctl", "job", "build", "team/"]).assert_success();
+    let out = complete(&mock, &["jenkinsctl", "job", "rm", "team/w"]);
+    out.assert_success();
+    assert_eq!(out.stdout, "team/web\n");
+
+    assert_eq!(mock.requests().len(), 1);
+}
+
+#[test]
+fn complete_nodes_and_views() {
+    let mock = MockJenkins::start();
+    mock.get(
+        "computer/api/json",
+        Reply::json(&computers(&[
+            computer("built-in", &[], false),
+            computer("agent-1", &["linux"], false),
+            computer("agent-2", &["linux"], true),
+        ])),
+    )
+    .get(
+        "job/team/api/json",
+        Reply::json(&json!({
+            "views": [
+                { "_class": "hudson.model.AllView", "name": "all" },
+                { "_class": "hudson.model.ListView", "name": "ops" },
+            ],
+        })),
+    );
+
+    let out = complete(&mock, &["jenkinsctl", "node", "set", "agent"]);
+    out.assert_success();
+    assert_eq!(out.stdout, "agent-1\nagent-2\n");
+    assert_eq!(
+        mock.requests()[0].path,
+        "/computer/api/json?tree=computer[displayName]"
+    );
+
+    let out = complete(
+        &mock,
+        &["jenkinsctl", "view", "show", "--folder", "team", "o"],
+    );
+    out.assert_success();
+    assert_eq!(out.stdout, "ops\n");
+}
+
+#[test]
+fn leave_other_words_to_the_shell() {
+    let mock = MockJenkins::start();
+
+    for words in [
+        &["jenkinsctl", "jo"][..],
+        &["jenkinsctl", "job", "build", "team/api", ""],
+        &["jenkinsctl", "job", "build", "--"],
+        &["jenkinsctl", "node", "set", "agent-1", ""],
+        &["jenkinsctl", "script", "run", ""],
+    ] {
+        let out = complete(&mock, words);
+        assert_eq!(out.code, Some(1), "{words:?}");
+        assert!(out.stdout.is_empty());
+    }
+    assert!(mock.requests().is_empty());
+}
+
+#[test]
+fn print_completion_scripts() {
+    let mock = MockJenkins::start();
+
+    let out = mock.run(&["completion", "bash"]);
+    out.assert_success();
+    assert!(out.stdout.contains("_jenkinsctl()"));
+    assert!(out
+        .stdout
+        .contains("complete -F _jenkinsctl_names -o bashdefault -o default jenkinsctl"));
+
+    let out = mock.run(&["completion", "zsh"]);
+    out.assert_success();
+    assert!(out.stdout.starts_with("#compdef jenkinsctl"));
+    assert!(out.stdout.contains("compdef _jenkinsctl_names jenkinsctl"));
+
+    let out = mock.run(&["completion", "fish"]);
+    out.assert_success();
+    assert!(out.stdout.contains("function __jenkinsctl_names"));
+
+    assert!(mock.requests().is_empty());
+}
@@ -0,0 +1,252 @@
+mod common;
+
+use common::{MockJenkins, Reply};
+use serde_json::json;
+
+const SYSTEM: &str = "credentials/store/system/domain/_";
+
+#[test]
+fn list_credentials() {
+    let mock = MockJenkins::start();
+    mock.get(
+        &format!("{SYSTEM}/api/json"),
+        Reply::json(&json!({
+            "credentials": [
+                { "id": "deploy", "typeName": "Username with password", "description": "Deploy user" },
+                { "id": "gh-token", "typeName": "Secret text", "description": null },
+            ],
+        })),
+    )
+    .get(
+        &format!("{SYSTEM}/credential/deploy/config.xml"),
+        Reply::text("<c><scope>GLOBAL</scope><id>deploy</id><password><secret-redacted/></password></c>"),
+    );
+
+    let out = mock.run(&["credentials", "list"]);
+    out.assert_success();
+    assert_eq!(
+        out.stdout,
+        format!(
+            "{:<40}{:<40}{:<10}Deploy user\n{:<40}{:<40}{:<10}\n",
+            "deploy", "Username with password", "GLOBAL", "gh-token", "Secret text", "-"
+        )
+    );
+    assert_eq!(
+        mock.requests()[0].path,
+        format!("/{SYSTEM}/api/json?tree=credentials[id,typeName,description]")
+    );
+}
+
+#[test]
+fn list_folder_credentials() {
+    let mock = MockJenkins::start();
+    mock.get(
+        "job/team/credentials/store/folder/domain/_/api/json",
+        Reply::json(&json!({ "credentials": [] })),
+    );
+
+    mock.run(&["credentials", "list", "--folder", "team"])
+        .assert_success();
+}
+
+#[test]
+fn create_credentials_from_stdin() {
+    let mock = MockJenkins::start();
+    mock.post(
+        "credentials/store/system/domain/_/createCredentials",
+        Reply::ok(),
+    );
+
+    let out = mock.run_with_input(
+        &[
+            "credentials",
+            "create",
+            "gh-token",
+            "--type",
+            "secret-text",
+            "--description",
+            "GitHub",
+        ],
+        "ghp_s3cr3t\n",
+    );
+    out.assert_success();
+    assert!(!out.stdout.contains("ghp_s3cr3t") && !out.stderr.contains("ghp_s3cr3t"));
+
+    let posts = mock.posts();
+    assert_eq!(posts[0].path, format!("/{SYSTEM}/createCredentials"));
+    assert_eq!(posts[0].header("content-type"), Some("application/xml"));
+    assert!(posts[0].body.contains(
+        "<scope>GLOBAL</scope><id>gh-token</id><description>GitHub</description><secret>ghp_s3cr3t</secret>"
+    ));
+}
+
+#[test]
+fn update_credentials_from_a_file() {
+    let mock = MockJenkins::start();
+    mock.post(
+        "job/team/credentials/store/folder/domain/_/credential/deploy/config.xml",
+        Reply::ok(),
+    );
+    std::fs::write(mock.dir().join("password"), "hunter2\n").unwrap();
+
+    mock.run(&[
+        "credentials",
+        "update",
+        "deploy",
+        "--type",
+        "username-password",
+        "--username",
+        "bob",
+        "--secret-file",
+        "password",
+        "--scope",
+        "system",
+        "--folder",
+        "team",
+    ])
+    .assert_success();
+
+    let posts = mock.posts();
+    assert_eq!(
+        posts[0].path,
+        "/job/team/credentials/store/folder/domain/_/credential/deploy/config.xml"
+    );
+    assert!(posts[0].body.contains("<scope>SYSTEM</scope>"));
+    assert!(posts[0]
+        .body
+        .contains("<username>bob</username><password>hunter2</password>"));
+}
+
+#[test]
+fn create_secret_file_credentials() {
+    let mock = MockJenkins::start();
+    mock.post(
+        "credentials/store/system/domain/_/createCredentials",
+        Reply::ok(),
+    );
+    std::fs::write(mock.dir().join("kubeconfig"), "apiVersion: v1\n").unwrap();
+
+    mock.run(&[
+        "credentials",
+        "create",
+        "kube",
+        "--type",
+        "secret-file",
+        "--secret-file",
+        "kubeconfig",
+    ])
+    .assert_success();
+    let out = mock.run_with_input(
+        &["credentials", "create", "kube", "--type", "secret-file"],
+        "data",
+    );
+    out.assert_failure();
+    assert!(out.stderr.contains("--file-name is required"));
+
+    let posts = mock.posts();
+    assert_eq!(posts.len(), 1);
+    assert!(posts[0].body.contains(
+        "<fileName>kubeconfig</fileName><secretBytes>YXBpVmVyc2lvbjogdjEK</secretBytes>"
+    ));
+}
+
+#[test]
+fn delete_credentials() {
+    let mock = MockJenkins::start();
+    mock.post(
+        "credentials/store/system/domain/prod/credential/deploy/doDelete",
+        Reply::ok(),
+    );
+
+    let out = mock.run(&["credentials", "delete", "deploy"]);
+    assert!(out.stderr.contains("credentials removal aborted"));
+    mock.run(&["credentials", "delete", "-y", "deploy", "--domain", "prod"])
+        .assert_success();
+
+    assert_eq!(
+        mock.post_paths(),
+        ["/credentials/store/system/domain/prod/credential/deploy/doDelete"]
+    );
+}
+
+#[test]
+fn rejected_credentials_changes() {
+    let mock = MockJenkins::start();
+    mock.post(
+        "credentials/store/system/domain/_/createCredentials",
+        Reply::status(409),
+    );
+
+    let out = mock.run_with_input(
+        &["credentials", "create", "gh-token", "--type", "secret-text"],
+        "s3cret\n",
+    );
+    out.assert_failure();
+    assert!(out.stderr.contains("409 Conflict"), "{}", out.stderr);
+    assert!(!out.stderr.contains("created credentials"));
+
+    let out = mock.run(&["credentials", "delete", "-y", "nope"]);
+    out.assert_failure();
+    assert!(
+        out.stderr
+            .contains("failed to delete credentials nope: 404 Not Found"),
+        "{}",
+        out.stderr
+    );
+}
+
+#[test]
+fn credentials_ids_are_encoded() {
+    let mock = MockJenkins::start();
+    let credential = "credentials/store/system/domain/my%20domain/credential/a%2Fb%20%231%3F%25";
+    mock.post(&format!("{credential}/config.xml"), Reply::ok())
+        .post(&format!("{credential}/doDelete"), Reply::ok());
+
+    mock.run_with_input(
+        &[
+            "credentials",
+            "update",
+            "a/b #1?%",
+            "--type",
+            "secret-text",
+            "--domain",
+            "my domain",
+        ],
+        "s3cret\n",
+    )
+    .assert_success();
+    mock.run(&[
+        "credentials",
+        "delete",
+        "-y",
+        "a/b #1?%",
+        "--domain",
+        "my domain",
+    ])
+    .assert_success();
+
+    assert_eq!(
+        mock.post_paths(),
+        [
+            format!("/{credential}/config.xml"),
+            format!("/{credential}/doDelete"),
+        ]
+    );
+}
+
+#[test]
+fn empty_secret() {
+    let mock = MockJenkins::start();
+
+    let out = mock.run_with_input(
+        &["credentials", "create", "gh-token", "--type", "secret-text"],
+        "\n",
+    );
+    out.assert_failure();
+    assert!(
+        out.stderr.contains("the secret read from stdin is empty"),
+        "{}",
+        out.stderr
+    );
+    assert!(mock.requests().is_empty());
+}
@@ -0,0 +1,310 @@
+mod common;
+
+use base64::Engine as _;
+use common::{job, MockJenkins, Reply, TOKEN, USER};
+use serde_json::json;
+
+#[test]
+fn basic_auth_and_extra_headers() {
+    let mock = MockJenkins::start();
+
+    mock.run(&["-H", "X-Forwarded-User: bob", "shutdown", "off"])
+        .assert_success();
+
+    let credentials = base64::engine::general_purpose::STANDARD.encode(format!("{USER}:{TOKEN}"));
+    let request = &mock.requests()[0];
+    assert_eq!(
+        request.header("authorization"),
+        Some(format!("Basic {credentials}").as_str())
+    );
+    assert_eq!(request.header("x-forwarded-user"), Some("bob"));
+
+    // Standard alphabet, not the url safe one
+    common::Output::of(
+        mock.command()
+            .env("JENKINS_TOKEN", "~~~>>>???")
+            .args(["shutdown", "off"]),
+        None,
+    )
+    .assert_success();
+    assert_eq!(
+        mock.requests()[1].header("authorization"),
+        Some("Basic YWRtaW46fn5+Pj4+Pz8/")
+    );
+}
+
+#[test]
+fn anonymous_and_bearer_auth() {
+    let mock = MockJenkins::start();
+
+    mock.run(&["--auth", "anonymous", "shutdown", "off"])
+        .assert_success();
+    mock.run(&["--auth", "bearer", "shutdown", "off"])
+        .assert_success();
+
+    let requests = mock.requests();
+    assert_eq!(requests[0].header("authorization"), None);
+    assert_eq!(
+        requests[1].header("authorization"),
+        Some(format!("Bearer {TOKEN}").as_str())
+    );
+}
+
+#[test]
+fn csrf_crumb_is_fetched_and_sent() {
+    let mock = MockJenkins::start();
+    mock.post(
+        "quietDown",
+        Reply::status(403).body("No valid crumb was included in the request"),
+    )
+    .post("quietDown", Reply::ok())
+    .get(
+        "crumbIssuer/api/json",
+        Reply::json(&json!({
+            "_class": "hudson.security.csrf.DefaultCrumbIssuer",
+            "crumb": "5f3a9c",
+            "crumbRequestField": "Jenkins-Crumb",
+        }))
+        .header("Set-Cookie", "JSESSIONID.1a2b=node0abc; Path=/; HttpOnly"),
+    );
+
+    mock.run(&["--auth", "anonymous", "shutdown", "on"])
+        .assert_success();
+
+    let requests = mock.requests();
+    let paths = requests.iter().map(|r| r.path.as_str()).collect::<Vec<_>>();
+    assert_eq!(paths, ["/quietDown", "/crumbIssuer/api/json", "/quietDown"]);
+    assert_eq!(requests[0].header("jenkins-crumb"), None);
+    assert_eq!(requests[2].header("jenkins-crumb"), Some("5f3a9c"));
+    assert_eq!(
+        requests[2].header("cookie"),
+        Some("JSESSIONID.1a2b=node0abc")
+    );
+}
+
+#[test]
+fn forbidden_reports_the_missing_permission() {
+    let mock = MockJenkins::start();
+    mock.post(
+        "computer/agent-1/doDelete",
+        Reply::status(403).body("Forbidden"),
+    );
+
+    let out = mock.run(&["node", "delete", "-y", "agent-1"]);
+    out.assert_failure();
+    assert!(
+        out.stderr.contains(
+            "permission denied (403) for /computer/agent-1/doDelete: \
+             the user is probably missing the Computer/Delete permission"
+        ),
+        "{}",
+        out.stderr
+    );
+    assert!(mock
+        .requests()
+        .iter()
+        .all(|r| r.path != "/crumbIssuer/api/json"));
+}
+
+#[test]
+fn unauthorized() {
+    let mock = MockJenkins::start();
+    mock.get("api/json", Reply::status(401));
+
+    let out = mock.run(&["job", "list"]);
+    out.assert_failure();
+    assert!(out
+        .stderr
+        .contains("authentication failed (401): check the user and token"));
+}
+
+#[test]
+fn gateway_errors_are_retried() {
+    let mock = MockJenkins::start();
+    mock.get("api/json", Reply::status(503).header("Retry-After", "0"))
+        .get(
+            "api/json",
+            Reply::json(&json!({ "jobs": [job("hudson.model.FreeStyleProject", "api")] })),
+        );
+
+    let out = mock.run(&["job", "list"]);
+    out.assert_success();
+    assert_eq!(out.stdout, "api\n");
+    assert!(out.stderr.contains("503 Service Unavailable, retry 1/3"));
+    assert_eq!(mock.requests().len(), 2);
+}
+
+#[test]
+fn posts_are_not_retried() {
+    let mock = MockJenkins::start();
+    mock.post("restart", Reply::status(503).header("Retry-After", "0"));
+
+    mock.run(&["restart", "--hard"]).assert_success();
+    assert_eq!(mock.requests().len(), 1);
+}
+
+#[test]
+fn controller_under_a_context_path() {
+    let mock = MockJenkins::with_prefix("/jenkins");
+    mock.get(
+        "api/json",
+        Reply::json(
+            &json!({ "jobs": [job("com.cloudbees.hudson.plugins.folder.Folder", "my team")] }),
+        ),
+    )
+    .get(
+        "job/my%20team/api/json",
+        Reply::json(
+            &json!({ "jobs": [job("hudson.model.FreeStyleProject", "my team/déploy 50%")] }),
+        ),
+    );
+
+    let out = mock.run(&["job", "list"]);
+    out.assert_success();
+    assert_eq!(out.stdout, "my team => déploy 50%\n");
+
+    mock.run(&["job", "kill", "my team/déploy 50%", "3"])
+        .assert_success();
+    assert_eq!(
+        mock.post_paths(),
+        ["/jenkins/job/my%20team/job/d%C3%A9ploy%2050%25/3/term"]
+    );
+}
+
+#[test]
+fn missing_url() {
+    let mock = MockJenkins::start();
+    let mut command = std::process::Command::new(env!("CARGO_BIN_EXE_jenkinsctl"));
+    command
+        .args(["job", "list"])
+        .env("JENKINSCTL_CONFIG", mock.dir().join("config.toml"))
+        .env_remove("JENKINS_URL");
+
+    let out = common::Output::of(&mut command, None);
+    out.assert_failure();
+    assert!(out.stderr.contains("missing argument: url"));
+}
+
+#[test]
+fn duration_out_of_range() {
+    let mock = MockJenkins::start();
+
+    let out = mock.run(&["--timeout", "99999999999999999d", "job", "list"]);
+    out.assert_failure();
+    assert!(out.stderr.contains("duration too large"), "{}", out.stderr);
+    assert!(mock.requests().is_empty());
+}
+
+#[test]
+fn stalled_body_times_out() {
+    let mock = MockJenkins::start();
+    mock.get("api/json", Reply::chunks(&[r#"{"jobs":["#]).stall());
+
+    let started = std::time::Instant::now();
+    let out = mock.run(&["--timeout", "1s", "job", "list"]);
+    out.assert_failure();
+    assert!(
+        out.stderr
+            .contains("no data received for /api/json within 1s (see --timeout)"),
+        "{}",
+        out.stderr
+    );
+    assert!(started.elapsed() < std::time::Duration::from_secs(10));
+}
+
+#[test]
+fn rate_limit_spaces_out_requests() {
+    let mock = MockJenkins::start();
+    mock.get(
+        "api/json",
+        Reply::json(&json!({ "jobs": [
+            job("com.cloudbees.hudson.plugins.folder.Folder", "a"),
+            job("com.cloudbees.hudson.plugins.folder.Folder", "b"),
+        ] })),
+    )
+    .get("job/a/api/json", Reply::json(&json!({ "jobs": [] })))
+    .get("job/b/api/json", Reply::json(&json!({ "jobs": [] })));
+
+    mock.run(&["--rate-limit", "4", "job", "list"])
+        .assert_success();
+    let requests = mock.requests();
+    assert_eq!(requests.len(), 3);
+    for pair in requests.windows(2) {
+        let gap = pair[1].at.duration_since(pair[0].at);
+        assert!(
+            gap >= std::time::Duration::from_millis(200),
+            "{} then {} after {gap:?}",
+            pair[0].path,
+            pair[1].path
+        );
+    }
+
+    let out = mock.run(&["--rate-limit", "0", "job", "list"]);
+    out.assert_failure();
+    assert!(
+        out.stderr.contains("invalid rate limit: 0"),
+        "{}",
+        out.stderr
+    );
+}
+
+#[test]
+fn requests_through_a_proxy() {
+    let mock = MockJenkins::start();
+    let proxy = mock.url().replace("http://", "http://bob:p%40ss@");
+    let credentials = base64::engine::general_purpose::STANDARD.encode("bob:p@ss");
+
+    // Plain http is sent to the proxy in absolute form
+    common::Output::of(
+        mock.command()
+            .env("JENKINS_URL", "http://jenkins.invalid:8080")
+            .args(["--proxy", &proxy, "shutdown", "off"]),
+        None,
+    )
+    .assert_success();
+    let request = &mock.requests()[0];
+    assert_eq!(
+        request.target,
+        "http://jenkins.invalid:8080/cancelQuietDown"
+    );
+    assert_eq!(
+        request.header("proxy-authorization"),
+        Some(format!("Basic {credentials}").as_str())
+    );
+
+    // https is tunnelled with CONNECT, the mock refuses it
+    let out = common::Output::of(
+        mock.command()
+            .env("JENKINS_URL", "https://jenkins.invalid")
+            .args(["--proxy", &proxy, "--retries", "0", "shutdown", "off"]),
+        None,
+    );
+    out.assert_failure();
+    assert!(
+        out.stderr
+            .contains("the proxy refused to connect to jenkins.invalid:443"),
+        "{}",
+        out.stderr
+    );
+    let request = &mock.requests()[1];
+    assert_eq!(request.method, "CONNECT");
+    assert_eq!(request.target, "jenkins.invalid:443");
+    assert_eq!(
+        request.header("proxy-authorization"),
+        Some(format!("Basic {credentials}").as_str())
+    );
+}
+
+#[test]
+fn insecure_skip_verify_warns() {
+    let mock = MockJenkins::start();
+
+    let out = mock.run(&["--insecure-skip-verify", "shutdown", "off"]);
+    out.assert_success();
+    assert!(
+        out.stderr
+            .contains("WARNING: TLS certificate verification is disabled"),
+        "{}",
+        out.stderr
+    );
+}
@@ -0,0 +1,292 @@
+mod common;
+
+use common::{job, queue_item, MockJenkins, Reply};
+use serde_json::json;
+
+const FOLDER: &str = "com.cloudbees.hudson.plugins.folder.Folder";
+const PIPELINE: &str = "org.jenkinsci.plugins.workflow.job.WorkflowJob";
+const FREESTYLE: &str = "hudson.model.FreeStyleProject";
+
+#[test]
+fn list_walks_nested_folders() {
+    let mock = MockJenkins::start();
+    mock.get(
+        "api/json",
+        Reply::json(&json!({ "jobs": [job(FREESTYLE, "standalone"), job(FOLDER, "team")] })),
+    )
+    .get(
+        "job/team/api/json",
+        Reply::json(&json!({ "jobs": [job(PIPELINE, "team/api"), job(FOLDER, "team/infra")] })),
+    )
+    .get(
+        "job/team/job/infra/api/json",
+        Reply::json(&json!({ "jobs": [job(PIPELINE, "team/infra/deploy")] })),
+    );
+
+    let out = mock.run(&["job", "list"]);
+    out.assert_success();
+    assert_eq!(
+        out.stdout,
+        "standalone\nteam => api\nteam => infra => deploy\n"
+    );
+
+    let walked = mock
+        .requests()
+        .into_iter()
+        .map(|r| r.path)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        walked,
+        [
+            "/api/json?tree=jobs[fullDisplayName,fullName,name]",
+            "/job/team/api/json?tree=jobs[fullDisplayName,fullName,name]",
+            "/job/team/job/infra/api/json?tree=jobs[fullDisplayName,fullName,name]",
+        ]
+    );
+}
+
+#[test]
+fn list_builds_of_a_job() {
+    let mock = MockJenkins::start();
+    mock.get(
+        "job/team/job/api/api/json",
+        Reply::json(&json!({
+            "_class": PIPELINE,
+            "builds": [
+                { "_class": "hudson.model.Run", "number": 3, "url": "http://ci/job/team/job/api/3/" },
+                { "_class": "hudson.model.Run", "number": 2, "url": "http://ci/job/team/job/api/2/" },
+            ],
+            "nextBuildNumber": 4,
+        })),
+    );
+
+    let out = mock.run(&["job", "list", "team/api"]);
+    out.assert_success();
+    assert_eq!(out.stdout, "3\n2\n");
+}
+
+#[test]
+fn list_builds_of_a_missing_job() {
+    let mock = MockJenkins::start();
+
+    let out = mock.run(&["job", "list", "team/nope"]);
+    out.assert_failure();
+    assert!(out.stderr.contains("404"), "{}", out.stderr);
+}
+
+fn queue(mock: &MockJenkins, id: u64) {
+    mock.get(
+        &format!("queue/item/{id}/api/json"),
+        Reply::json(&queue_item(id, "http://ci/job/team/job/api/")),
+    );
+}
+
+fn created(mock: &MockJenkins, id: u64) -> Reply {
+    Reply::status(201).header("Location", &format!("{}/queue/item/{id}/", mock.url()))
+}
+
+#[test]
+fn build_without_parameters() {
+    let mock = MockJenkins::start();
+    mock.post("job/team/job/api/build", created(&mock, 12));
+    queue(&mock, 12);
+
+    let out = mock.run(&["job", "build", "team/api"]);
+    out.assert_success();
+    assert!(out.stderr.contains("queued as item 12"), "{}", out.stderr);
+    assert_eq!(mock.post_paths(), ["/job/team/job/api/build?delay=0sec"]);
+}
+
+#[test]
+fn build_with_parameters() {
+    let mock = MockJenkins::start();
+    mock.post("job/team/job/api/buildWithParameters", created(&mock, 13));
+    queue(&mock, 13);
+
+    mock.run(&["job", "build", "team/api", "env=prod,tag=v1 rc&2,dry="])
+        .assert_success();
+    mock.run(&["job", "build", "team/api", "-"])
+        .assert_success();
+
+    assert_eq!(
+        mock.post_paths(),
+        [
+            "/job/team/job/api/buildWithParameters?delay=0sec&env=prod&tag=v1%20rc%262&dry=",
+            "/job/team/job/api/buildWithParameters?delay=0sec",
+        ]
+    );
+}
+
+#[test]
+fn build_of_a_missing_job() {
+    let mock = MockJenkins::start();
+
+    let out = mock.run(&["job", "build", "team/nope"]);
+    out.assert_failure();
+    assert!(
+        out.stderr.contains("failed to build team/nope: 404"),
+        "{}",
+        out.stderr
+    );
+}
+
+#[test]
+fn build_and_follow_the_chunked_console() {
+    let mock = MockJenkins::start();
+    let mut item = queue_item(14, "http://ci/job/team/job/api/");
+    item["executable"] = json!({ "number": 5, "url": "http://ci/job/team/job/api/5/" });
+
+    mock.post("job/team/job/api/build", created(&mock, 14))
+        .get("queue/item/14/api/json", Reply::json(&item))
+        .get(
+            "job/team/job/api/5/logText/progressiveText?start=0",
+            Reply::chunks(&["Started by user admin\n", "[Pipeline] sh\n"])
+                .header("X-Text-Size", "36")
+                .header("X-More-Data", "true"),
+        )
+        .get(
+            "job/team/job/api/5/logText/progressiveText?start=36",
+            Reply::chunks(&["+ make\n", "Finished: SUCCESS\n"]).header("X-Text-Size", "61"),
+        );
+
+    let out = mock.run(&["job", "build", "-f", "team/api"]);
+    out.assert_success();
+    assert!(out.stderr.contains("started build 5"), "{}", out.stderr);
+    assert_eq!(
+        out.stdout,
+        "Started by user admin\n[Pipeline] sh\n+ make\nFinished: SUCCESS\n"
+    );
+}
+
+#[test]
+fn remove_deletes_the_job() {
+    let mock = MockJenkins::start();
+    mock.on("DELETE", "job/team/job/api/", Reply::ok());
+
+    mock.run(&["job", "remove", "team/api"]).assert_success();
+
+    let posts = mock.posts();
+    assert_eq!(posts.len(), 1);
+    assert_eq!(posts[0].method, "DELETE");
+    assert_eq!(posts[0].path, "/job/team/job/api/");
+}
+
+#[test]
+fn download_artifacts_of_a_build() {
+    let mock = MockJenkins::start();
+    let zip = b"PK\x03\x04archive";
+    mock.get(
+        "job/team/job/api/5/artifact/*zip*/archive.zip",
+        Reply::bytes(zip),
+    );
+
+    mock.run(&["job", "download", "team/api", "5", "artifact"])
+        .assert_success();
+
+    assert_eq!(std::fs::read(mock.dir().join("api_5.zip")).unwrap(), zip);
+}
+
+#[test]
+fn download_artifacts_of_a_build_range() {
+    let mock = MockJenkins::start();
+    mock.get(
+        "job/team/job/api/3/artifact/*zip*/archive.zip",
+        Reply::bytes(b"PK3"),
+    )
+    .get(
+        "job/team/job/api/5/artifact/*zip*/archive.zip",
+        Reply::bytes(b"PK5"),
+    );
+
+    let out = mock.run(&["job", "download", "team/api", "3..=5", "artifact"]);
+    out.assert_success();
+    assert!(
+        out.stderr.contains("artifacts not found for the build 4"),
+        "{}",
+        out.stderr
+    );
+
+    assert_eq!(std::fs::read(mock.dir().join("api_3.zip")).unwrap(), b"PK3");
+    assert!(!mock.dir().join("api_4.zip").exists());
+    assert_eq!(std::fs::read(mock.dir().join("api_5.zip")).unwrap(), b"PK5");
+}
+
+#[test]
+fn download_log_of_a_build() {
+    let mock = MockJenkins::start();
+    mock.get(
+        "job/team/job/api/5/consoleText",
+        Reply::chunks(&["Started\n", "Finished: FAILURE\n"]),
+    );
+
+    let out = mock.run(&["job", "download", "team/api", "5", "log"]);
+    out.assert_success();
+    assert_eq!(out.stdout, "Started\nFinished: FAILURE\n");
+}
+
+#[test]
+fn kill_maps_signals_to_actions() {
+    let mock = MockJenkins::start();
+
+    mock.run(&["job", "kill", "team/api", "5"]).assert_success();
+    mock.run(&["job", "kill", "-s", "KILL", "team/api", "5"])
+        .assert_success();
+    mock.run(&["job", "kill", "-s", "1", "team/api", "5"])
+        .assert_success();
+    let out = mock.run(&["job", "kill", "-s", "USR1", "team/api", "5"]);
+    assert!(
+        out.stderr.contains("invalid signal: USR1"),
+        "{}",
+        out.stderr
+    );
+
+    assert_eq!(
+        mock.post_paths(),
+        [
+            "/job/team/job/api/5/term",
+            "/job/team/job/api/5/kill",
+            "/job/team/job/api/5/stop",
+        ]
+    );
+}
+
+#[test]
+fn rebuild_reuses_the_parameters() {
+    let mock = MockJenkins::start();
+    mock.get(
+        "job/team/job/api/5/api/json?tree=actions",
+        Reply::json(&json!({
+            "_class": "org.jenkinsci.plugins.workflow.job.WorkflowRun",
+            "actions": [
+                { "_class": "hudson.model.CauseAction" },
+                { "_class": "hudson.model.ParametersAction" },
+            ],
+        })),
+    )
+    .get(
+        "job/team/job/api/5/api/json?tree=actions[parameters[name,value]]{1}",
+        Reply::json(&json!({
+            "_class": "org.jenkinsci.plugins.workflow.job.WorkflowRun",
+            "actions": [{
+                "_class": "hudson.model.ParametersAction",
+                "parameters": [
+                    { "_class": "hudson.model.StringParameterValue", "name": "env", "value": "prod" },
+                    { "_class": "hudson.model.BooleanParameterValue", "name": "dry", "value": false },
+                ],
+            }],
+        })),
+    )
+    .post(
+        "job/team/job/api/buildWithParameters",
+        created(&mock, 15),
+    );
+    queue(&mock, 15);
+
+    let out = mock.run(&["job", "rebuild", "team/api", "5"]);
+    out.assert_success();
+    assert!(out.stderr.contains("queued as item 15"), "{}", out.stderr);
+    assert_eq!(
+        mock.post_paths(),
+        ["/job/team/job/api/buildWithParameters?delay=0sec&env=prod&dry=false"]
+    );
+}
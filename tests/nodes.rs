@@ -0,0 +1,472 @@
+mod common;
+
+use common::{computer, computers, MockJenkins, Reply};
+use serde_json::json;
+
+fn nodes(mock: &MockJenkins) {
+    mock.get(
+        "computer/api/json",
+        Reply::json(&computers(&[
+            computer("agent-1", &["linux", "docker"], false),
+            computer("agent-2", &["linux"], true),
+            computer("win-1", &["windows"], false),
+        ])),
+    );
+}
+
+fn node(mock: &MockJenkins, name: &str, replies: &[serde_json::Value]) {
+    for reply in replies {
+        mock.get(&format!("computer/{name}/api/json"), Reply::json(reply));
+    }
+}
+
+#[test]
+fn show_node() {
+    let mock = MockJenkins::start();
+    node(&mock, "agent-1", &[computer("agent-1", &["linux"], false)]);
+
+    let out = mock.run(&["node", "show", "agent-1"]);
+    out.assert_success();
+    assert!(out.stdout.contains(&format!("{:.<30}agent-1\n", "name")));
+    assert!(out.stdout.contains(&format!("{:.<30}online\n", "status")));
+    assert!(out
+        .stdout
+        .contains(&format!("{:.<30}linux agent-1\n", "labels")));
+    assert!(out.stdout.contains("free (/var/lib/jenkins)"));
+    assert!(out
+        .stdout
+        .contains(&format!("{:.<30}42ms\n", "response time")));
+    assert!(out
+        .stdout
+        .contains(&format!("{:.<30}Linux (amd64)\n", "architecture")));
+
+    let out = mock.run(&["node", "show", "agent-1", "--min-disk", "1T"]);
+    out.assert_success();
+    assert!(out.stdout.contains("free disk space"), "{}", out.stdout);
+}
+
+#[test]
+fn show_missing_node() {
+    let mock = MockJenkins::start();
+
+    let out = mock.run(&["node", "show", "nope"]);
+    out.assert_failure();
+    assert!(out.stderr.contains("404"), "{}", out.stderr);
+}
+
+#[test]
+fn node_names_are_encoded() {
+    let mock = MockJenkins::start();
+    node(&mock, "gpu%20%231%3F", &[computer("gpu #1?", &[], false)]);
+    mock.post("computer/gpu%20%231%3F/doDelete", Reply::ok());
+
+    mock.run(&["node", "show", "gpu #1?"]).assert_success();
+    mock.run(&["node", "delete", "-y", "gpu #1?"])
+        .assert_success();
+    assert_eq!(mock.post_paths(), ["/computer/gpu%20%231%3F/doDelete"]);
+}
+
+#[test]
+fn size_out_of_range() {
+    let mock = MockJenkins::start();
+
+    let out = mock.run(&["node", "show", "agent-1", "--min-disk", "99999999T"]);
+    out.assert_failure();
+    assert!(out.stderr.contains("size too large"), "{}", out.stderr);
+    assert!(mock.requests().is_empty());
+}
+
+#[test]
+fn show_raw_and_executors() {
+    let mock = MockJenkins::start();
+    nodes(&mock);
+
+    let out = mock.run(&["node", "show", "raw"]);
+    out.assert_success();
+    assert!(
+        out.stdout.contains("display_name: \"win-1\""),
+        "{}",
+        out.stdout
+    );
+
+    let out = mock.run(&["node", "show", "executors"]);
+    out.assert_success();
+    assert_eq!(
+        out.stdout,
+        "Total number of executors: 4\nBusy executors: 1\n"
+    );
+
+    let out = mock.run(&["node", "show", "executors", "--busy"]);
+    out.assert_success();
+    assert_eq!(out.stdout, "Busy executors: 1\n");
+}
+
+#[test]
+fn health_reports_warnings() {
+    let mock = MockJenkins::start();
+    nodes(&mock);
+
+    let out = mock.run(&["node", "health"]);
+    out.assert_success();
+    assert!(out.stdout.contains(&format!("{:.<40}ok\n", "agent-1")));
+    assert!(out.stdout.contains(&format!("{:.<40}warning\n", "agent-2")));
+    assert!(out.stdout.contains("    node is offline\n"));
+
+    let out = mock.run(&["node", "health", "--selector", "windows"]);
+    out.assert_success();
+    assert!(!out.stdout.contains("agent-"), "{}", out.stdout);
+    assert!(out.stdout.contains("win-1"));
+
+    let out = mock.run(&["node", "health", "--fail-on-warning"]);
+    assert_eq!(out.code, Some(1));
+}
+
+#[test]
+fn list_nodes() {
+    let mock = MockJenkins::start();
+    let mut offline = computer("agent-2", &["linux"], true);
+    offline["temporarilyOffline"] = json!(true);
+    offline["offlineCauseReason"] = json!("maintenance");
+    offline["offlineCause"] = json!({
+        "_class": "hudson.slaves.OfflineCause$UserCause",
+        "description": "Disconnected by bob : maintenance",
+        "timestamp": 1_700_000_000_000_u64,
+    });
+    mock.get(
+        "computer/api/json",
+        Reply::json(&computers(&[
+            computer("agent-1", &["linux"], false),
+            offline,
+            computer("win-1", &["windows"], false),
+        ])),
+    );
+
+    let out = mock.run(&["node", "list"]);
+    out.assert_success();
+    assert_eq!(out.stdout, "agent-1\nagent-2\nwin-1\n");
+
+    let out = mock.run(&["node", "list", "--selector", "label=linux && !name=agent-1"]);
+    out.assert_success();
+    assert_eq!(out.stdout, "agent-2\n");
+
+    let out = mock.run(&["node", "list", "--offline-only"]);
+    out.assert_success();
+    let lines = out.stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 1, "{}", out.stdout);
+    assert!(lines[0].starts_with(&format!("{:.<40}temporarily offline", "agent-2")));
+    assert!(lines[0].ends_with("by bob (maintenance)"), "{}", lines[0]);
+
+    let out = mock.run(&["node", "list", "--status"]);
+    out.assert_success();
+    assert!(out.stdout.contains(&format!("{:.<40}online\n", "win-1")));
+}
+
+#[test]
+fn set_node_state() {
+    let mock = MockJenkins::start();
+    let mut offline = computer("agent-1", &[], false);
+    offline["temporarilyOffline"] = json!(true);
+    node(
+        &mock,
+        "agent-1",
+        &[
+            computer("agent-1", &[], false),
+            computer("agent-1", &[], false),
+            computer("agent-1", &[], false),
+            offline,
+        ],
+    );
+    mock.post("computer/agent-1/toggleOffline", Reply::ok())
+        .post("computer/agent-1/launchSlaveAgent", Reply::ok())
+        .post("computer/agent-1/doDisconnect", Reply::ok());
+
+    let out = mock.run(&["node", "set", "agent-1", "offline", "maint window"]);
+    out.assert_success();
+    assert!(out.stderr.contains("node agent-1 taken offline"));
+    let out = mock.run(&["node", "set", "agent-1", "online"]);
+    assert!(out.stderr.contains("node agent-1 is already online"));
+    mock.run(&["node", "set", "agent-1", "connect"])
+        .assert_success();
+    mock.run(&["node", "set", "agent-1", "disconnect", "bye"])
+        .assert_success();
+    mock.run(&["node", "set", "agent-1", "online"])
+        .assert_success();
+
+    assert_eq!(
+        mock.post_paths(),
+        [
+            "/computer/agent-1/toggleOffline?offlineMessage=maint%20window",
+            "/computer/agent-1/launchSlaveAgent",
+            "/computer/agent-1/doDisconnect?offlineMessage=bye",
+            "/computer/agent-1/toggleOffline",
+        ]
+    );
+}
+
+#[test]
+fn set_nodes_by_selector() {
+    let mock = MockJenkins::start();
+    nodes(&mock);
+    node(&mock, "agent-1", &[computer("agent-1", &[], false)]);
+    node(&mock, "agent-2", &[computer("agent-2", &[], false)]);
+    mock.post("computer/agent-1/toggleOffline", Reply::ok())
+        .post("computer/agent-2/toggleOffline", Reply::ok());
+
+    let out = mock.run(&["node", "set", "--selector", "linux", "offline"]);
+    out.assert_success();
+    assert!(out.stdout.contains(&format!(
+        "{:.<40}{:<8}node agent-1 taken offline",
+        "agent-1", "ok"
+    )));
+
+    let mut posts = mock.post_paths();
+    posts.sort();
+    assert_eq!(
+        posts,
+        [
+            "/computer/agent-1/toggleOffline",
+            "/computer/agent-2/toggleOffline",
+        ]
+    );
+}
+
+#[test]
+fn create_node_from_flags() {
+    let mock = MockJenkins::start();
+    mock.post("computer/doCreateItem", Reply::ok());
+
+    mock.run(&[
+        "node",
+        "create",
+        "agent-3",
+        "--remote-fs",
+        "/home/jenkins",
+        "--executors",
+        "2",
+        "--labels",
+        "linux,docker",
+        "--launcher",
+        "ssh",
+        "--host",
+        "10.0.0.3",
+        "--credentials-id",
+        "ssh-key",
+    ])
+    .assert_success();
+
+    let posts = mock.posts();
+    assert_eq!(posts.len(), 1);
+    assert_eq!(posts[0].path, "/computer/doCreateItem");
+    assert_eq!(
+        posts[0].header("content-type"),
+        Some("application/x-www-form-urlencoded")
+    );
+    let (fields, form) = posts[0].body.split_once("&json=").unwrap();
+    assert_eq!(fields, "name=agent-3&type=hudson.slaves.DumbSlave");
+    let form: serde_json::Value =
+        serde_json::from_str(&urlencoding::decode(form).unwrap()).unwrap();
+    assert_eq!(form["name"], "agent-3");
+    assert_eq!(form["numExecutors"], "2");
+    assert_eq!(form["labelString"], "linux docker");
+    assert_eq!(form["remoteFS"], "/home/jenkins");
+    assert_eq!(form["launcher"]["host"], "10.0.0.3");
+    assert_eq!(form["launcher"]["port"], "22");
+    assert_eq!(form["launcher"]["credentialsId"], "ssh-key");
+}
+
+#[test]
+fn create_node_from_config() {
+    let mock = MockJenkins::start();
+    mock.post("computer/doCreateItem", Reply::ok())
+        .post("computer/agent-4/config.xml", Reply::ok());
+    let config = "<slave><name>agent-4</name><remoteFS>/srv/jenkins</remoteFS></slave>";
+
+    mock.run_with_input(&["node", "create", "--file", "-"], config)
+        .assert_success();
+
+    let posts = mock.posts();
+    assert_eq!(posts.len(), 2);
+    assert_eq!(posts[0].path, "/computer/doCreateItem");
+    assert!(posts[0].body.starts_with("name=agent-4&"));
+    assert_eq!(posts[1].path, "/computer/agent-4/config.xml");
+    assert_eq!(posts[1].header("content-type"), Some("application/xml"));
+    assert_eq!(posts[1].body, config);
+}
+
+#[test]
+fn create_rejected_node() {
+    let mock = MockJenkins::start();
+    mock.post(
+        "computer/doCreateItem",
+        Reply::status(400).body("Agent called 'agent-4' already exists"),
+    );
+    let config = "<slave><name>agent-4</name></slave>";
+
+    let out = mock.run_with_input(&["node", "create", "--file", "-"], config);
+    out.assert_failure();
+    assert!(
+        out.stderr
+            .contains("failed to create node agent-4: 400 Bad Request"),
+        "{}",
+        out.stderr
+    );
+    assert!(!out.stderr.contains("created node"));
+    assert_eq!(mock.post_paths(), ["/computer/doCreateItem"]);
+}
+
+#[test]
+fn delete_nodes() {
+    let mock = MockJenkins::start();
+    nodes(&mock);
+    mock.post("computer/agent-1/doDelete", Reply::ok())
+        .post("computer/win-1/doDelete", Reply::ok());
+
+    let out = mock.run(&["node", "delete", "agent-1"]);
+    out.assert_success();
+    assert!(out.stdout.contains("Delete the node(s) agent-1? [y/N]"));
+    assert!(out.stderr.contains("node removal aborted"));
+    assert!(mock.posts().is_empty());
+
+    mock.run_with_input(&["node", "delete", "agent-1"], "y\n")
+        .assert_success();
+    mock.run(&["node", "delete", "-y", "--selector", "windows"])
+        .assert_success();
+
+    assert_eq!(
+        mock.post_paths(),
+        ["/computer/agent-1/doDelete", "/computer/win-1/doDelete"]
+    );
+}
+
+#[test]
+fn get_and_apply_node_config() {
+    let mock = MockJenkins::start();
+    mock.post("computer/agent-1/config.xml", Reply::ok());
+    let config = "<slave><name>agent-1</name></slave>\n";
+    mock.get("computer/agent-1/config.xml", Reply::text(config));
+
+    let out = mock.run(&["node", "config", "get", "agent-1"]);
+    out.assert_success();
+    assert_eq!(out.stdout, config);
+
+    std::fs::write(mock.dir().join("agent-1.xml"), config).unwrap();
+    mock.run(&["node", "config", "apply", "agent-1", "agent-1.xml"])
+        .assert_success();
+
+    let posts = mock.posts();
+    assert_eq!(posts[0].path, "/computer/agent-1/config.xml");
+    assert_eq!(posts[0].body, config);
+}
+
+#[test]
+fn add_and_remove_labels() {
+    let mock = MockJenkins::start();
+    mock.post("computer/agent-1/config.xml", Reply::ok());
+    mock.get(
+        "computer/agent-1/config.xml",
+        Reply::text("<slave>\n  <name>agent-1</name>\n  <label>linux</label>\n</slave>"),
+    );
+
+    mock.run(&["node", "labels", "add", "agent-1", "docker"])
+        .assert_success();
+    mock.run(&["node", "labels", "remove", "agent-1", "linux"])
+        .assert_success();
+    let out = mock.run(&["node", "labels", "add", "agent-1", "linux"]);
+    assert!(out
+        .stderr
+        .contains("node agent-1 already has the label linux"));
+
+    let bodies = mock.posts().into_iter().map(|p| p.body).collect::<Vec<_>>();
+    assert_eq!(
+        bodies,
+        [
+            "<slave>\n  <name>agent-1</name>\n  <label>linux docker</label>\n</slave>",
+            "<slave>\n  <name>agent-1</name>\n  <label></label>\n</slave>",
+        ]
+    );
+}
+
+#[test]
+fn drain_node() {
+    let mock = MockJenkins::start();
+    node(&mock, "agent-1", &[computer("agent-1", &[], false)]);
+    mock.post("computer/agent-1/toggleOffline", Reply::ok())
+        .post("computer/agent-1/doDisconnect", Reply::ok());
+
+    let out = mock.run(&["node", "drain", "agent-1", "--disconnect"]);
+    out.assert_success();
+    assert!(out
+        .stderr
+        .contains("node agent-1 is drained and disconnected"));
+    assert_eq!(
+        mock.post_paths(),
+        [
+            "/computer/agent-1/toggleOffline?offlineMessage=Drained%20by%20jenkinsctl",
+            "/computer/agent-1/doDisconnect?offlineMessage=Drained%20by%20jenkinsctl",
+        ]
+    );
+}
+
+#[test]
+fn drain_node_timeout() {
+    let mock = MockJenkins::start();
+    let mut busy = computer("agent-1", &[], false);
+    busy["idle"] = json!(false);
+    busy["temporarilyOffline"] = json!(true);
+    node(&mock, "agent-1", &[busy]);
+
+    let out = mock.run(&["node", "drain", "agent-1", "--timeout", "0s"]);
+    out.assert_failure();
+    assert!(out.stderr.contains("node agent-1 did not drain within"));
+    assert!(mock.posts().is_empty());
+}
+
+#[test]
+fn set_nodes_by_selector_reports_failures() {
+    let mock = MockJenkins::start();
+    nodes(&mock);
+    node(&mock, "agent-1", &[computer("agent-1", &[], false)]);
+    mock.post("computer/agent-1/toggleOffline", Reply::ok());
+
+    let out = mock.run(&["node", "set", "--selector", "linux", "offline"]);
+    out.assert_failure();
+    assert!(out
+        .stdout
+        .contains(&format!("{:.<40}{:<8}404", "agent-2", "failed")));
+    assert!(out.stderr.contains("1 of 2 node operations failed"));
+    assert_eq!(mock.post_paths(), ["/computer/agent-1/toggleOffline"]);
+}
+
+#[test]
+fn rejected_node_changes_fail() {
+    let mock = MockJenkins::start();
+    nodes(&mock);
+    node(&mock, "agent-1", &[computer("agent-1", &[], false)]);
+    node(&mock, "agent-2", &[computer("agent-2", &[], false)]);
+    mock.post("computer/agent-1/toggleOffline", Reply::ok())
+        .post("computer/agent-2/toggleOffline", Reply::status(500));
+
+    let out = mock.run(&["node", "set", "--selector", "linux", "offline"]);
+    out.assert_failure();
+    assert!(out.stdout.contains(&format!(
+        "{:.<40}{:<8}node agent-1 taken offline",
+        "agent-1", "ok"
+    )));
+    assert!(out.stdout.contains(&format!(
+        "{:.<40}{:<8}failed to take offline node agent-2: 500 Internal Server Error",
+        "agent-2", "failed"
+    )));
+    assert!(out.stderr.contains("1 of 2 node operations failed"));
+
+    // Draining stops at the failed toggle instead of polling
+    let out = mock.run(&["node", "drain", "agent-2"]);
+    out.assert_failure();
+    assert!(out
+        .stderr
+        .contains("failed to take offline node agent-2: 500 Internal Server Error"));
+
+    mock.post("computer/win-1/doDelete", Reply::status(500));
+    let out = mock.run(&["node", "delete", "-y", "win-1"]);
+    out.assert_failure();
+    assert!(!out.stderr.contains("node win-1 deleted"));
+}
@@ -0,0 +1,146 @@
+mod common;
+
+use common::{MockJenkins, Reply};
+use serde_json::json;
+
+fn plugins(mock: &MockJenkins) {
+    mock.get(
+        "pluginManager/api/json",
+        Reply::json(&json!({
+            "plugins": [
+                {
+                    "shortName": "workflow-job",
+                    "version": "1400.v7fd111b_ec82f",
+                    "active": true,
+                    "enabled": true,
+                    "hasUpdate": false,
+                    "dependencies": [
+                        { "shortName": "workflow-api", "version": "1291", "optional": false },
+                        { "shortName": "pipeline-groovy-lib", "version": "704", "optional": true },
+                    ],
+                },
+                {
+                    "shortName": "git",
+                    "version": "5.2.0",
+                    "active": true,
+                    "enabled": true,
+                    "pinned": true,
+                    "hasUpdate": true,
+                    "dependencies": [],
+                },
+                {
+                    "shortName": "docker-plugin",
+                    "version": "1.5",
+                    "active": false,
+                    "enabled": false,
+                    "hasUpdate": false,
+                    "dependencies": [],
+                },
+            ],
+        })),
+    );
+}
+
+#[test]
+fn list_plugins() {
+    let mock = MockJenkins::start();
+    plugins(&mock);
+
+    let out = mock.run(&["plugin", "list"]);
+    out.assert_success();
+    let lines = out.stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with(&format!("{:<40}{:<30}disabled", "docker-plugin", "1.5")));
+    assert!(lines[1].starts_with(&format!(
+        "{:<40}{:<30}{:<20}update available",
+        "git", "5.2.0", "enabled,pinned"
+    )));
+    assert!(lines[2].ends_with("workflow-api:1291, pipeline-groovy-lib:704 (optional)"));
+    assert_eq!(mock.requests()[0].path, "/pluginManager/api/json?depth=1");
+
+    let out = mock.run(&["plugin", "list", "--updates"]);
+    out.assert_success();
+    assert_eq!(out.stdout.lines().count(), 1);
+    assert!(out.stdout.starts_with("git "));
+}
+
+#[test]
+fn export_plugins() {
+    let mock = MockJenkins::start();
+    plugins(&mock);
+
+    let out = mock.run(&["plugin", "export"]);
+    out.assert_success();
+    assert_eq!(
+        out.stdout,
+        "docker-plugin:1.5\ngit:5.2.0\nworkflow-job:1400.v7fd111b_ec82f\n"
+    );
+}
+
+#[test]
+fn install_plugins() {
+    let mock = MockJenkins::start();
+    mock.post("pluginManager/installNecessaryPlugins", Reply::ok());
+
+    mock.run(&["plugin", "install", "git", "docker-plugin@1.5"])
+        .assert_success();
+
+    let posts = mock.posts();
+    assert_eq!(posts[0].path, "/pluginManager/installNecessaryPlugins");
+    assert_eq!(posts[0].header("content-type"), Some("text/xml"));
+    assert_eq!(
+        posts[0].body,
+        "<jenkins><install plugin=\"git@latest\" /><install plugin=\"docker-plugin@1.5\" /></jenkins>"
+    );
+}
+
+#[test]
+fn uninstall_enable_and_disable_plugins() {
+    let mock = MockJenkins::start();
+    mock.post("pluginManager/plugin/git/doUninstall", Reply::ok())
+        .post(
+            "pluginManager/plugin/docker-plugin/makeEnabled",
+            Reply::ok(),
+        )
+        .post(
+            "pluginManager/plugin/docker-plugin/makeDisabled",
+            Reply::ok(),
+        );
+
+    let out = mock.run(&["plugin", "uninstall", "git"]);
+    assert!(out.stderr.contains("plugin removal aborted"));
+    mock.run(&["plugin", "uninstall", "-y", "git"])
+        .assert_success();
+    mock.run(&["plugin", "enable", "docker-plugin"])
+        .assert_success();
+    mock.run(&["plugin", "disable", "docker-plugin"])
+        .assert_success();
+
+    assert_eq!(
+        mock.post_paths(),
+        [
+            "/pluginManager/plugin/git/doUninstall",
+            "/pluginManager/plugin/docker-plugin/makeEnabled",
+            "/pluginManager/plugin/docker-plugin/makeDisabled",
+        ]
+    );
+}
+
+#[test]
+fn unknown_plugin() {
+    let mock = MockJenkins::start();
+
+    let out = mock.run(&["plugin", "disable", "nope"]);
+    out.assert_failure();
+    assert!(
+        out.stderr
+            .contains("failed to disable the plugin nope: 404 Not Found"),
+        "{}",
+        out.stderr
+    );
+
+    mock.post("pluginManager/installNecessaryPlugins", Reply::status(500));
+    let out = mock.run(&["plugin", "install", "nope"]);
+    out.assert_failure();
+    assert!(!out.stderr.contains("scheduled"));
+}
@@ -0,0 +1,196 @@
+mod common;
+
+use common::{queue_item, MockJenkins, Reply};
+use serde_json::json;
+
+const MINUTE: u64 = 60_000;
+
+fn now() -> u64 {
+    jenkinsctl::human::now_millis()
+}
+
+fn running(mock: &MockJenkins) {
+    mock.get(
+        "computer/api/json",
+        Reply::json(&json!({
+            "computer": [
+                {
+                    "displayName": "agent-1",
+                    "executors": [
+                        {
+                            "number": 0,
+                            "currentExecutable": {
+                                "number": 7,
+                                "url": "http://ci/job/team/job/api/7/",
+                                "timestamp": now() - 10 * MINUTE,
+                                "estimatedDuration": 60 * MINUTE,
+                            },
+                        },
+                        { "number": 1, "currentExecutable": null },
+                    ],
+                    "oneOffExecutors": [],
+                },
+                {
+                    "displayName": "built-in",
+                    "executors": [],
+                    "oneOffExecutors": [{
+                        "number": 0,
+                        "currentExecutable": {
+                            "number": 3,
+                            "url": "http://ci/job/nightly/3/",
+                            "timestamp": now() - 120 * MINUTE,
+                            "estimatedDuration": -1,
+                        },
+                    }],
+                },
+            ],
+        })),
+    );
+}
+
+#[test]
+fn list_running_builds() {
+    let mock = MockJenkins::start();
+    running(&mock);
+
+    let out = mock.run(&["builds", "running"]);
+    out.assert_success();
+    let lines = out.stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2, "{}", out.stdout);
+    assert!(lines[0].starts_with(&format!(
+        "{:<40}{:<8}{:<20}{:<5}",
+        "team/api", "#7", "agent-1", 0
+    )));
+    assert!(lines[1].starts_with(&format!(
+        "{:<40}{:<8}{:<20}{:<5}",
+        "nightly", "#3", "built-in", 0
+    )));
+    assert!(lines[1].ends_with("unknown"));
+    assert!(mock.requests()[0]
+        .path
+        .starts_with("/computer/api/json?tree=computer[displayName,executors[number,"));
+
+    let out = mock.run(&["builds", "running", "--node", "agent-1"]);
+    out.assert_success();
+    assert_eq!(out.stdout.lines().count(), 1);
+}
+
+#[test]
+fn kill_long_running_builds() {
+    let mock = MockJenkins::start();
+    running(&mock);
+    mock.post("job/nightly/3/term", Reply::ok())
+        .post("job/team/job/api/7/kill", Reply::ok());
+
+    let out = mock.run(&["builds", "running", "--longer-than", "1h", "--kill"]);
+    out.assert_success();
+    assert!(out.stdout.starts_with("nightly"), "{}", out.stdout);
+    assert!(out.stderr.contains("interrupting build 3 of the nightly"));
+
+    mock.run(&[
+        "builds", "running", "--node", "agent-1", "--kill", "-s", "KILL",
+    ])
+    .assert_success();
+
+    assert_eq!(
+        mock.post_paths(),
+        ["/job/nightly/3/term", "/job/team/job/api/7/kill"]
+    );
+}
+
+#[test]
+fn failed_kill() {
+    let mock = MockJenkins::start();
+    running(&mock);
+    mock.post("job/nightly/3/term", Reply::status(500))
+        .post("job/team/job/api/7/term", Reply::ok());
+
+    let out = mock.run(&["builds", "running", "--kill"]);
+    out.assert_failure();
+    assert!(
+        out.stderr
+            .contains("failed to interrupt build 3 of nightly: 500"),
+        "{}",
+        out.stderr
+    );
+    assert!(
+        out.stderr.contains("1 of 2 interruptions failed"),
+        "{}",
+        out.stderr
+    );
+    assert_eq!(mock.post_paths().len(), 2);
+}
+
+fn queue(mock: &MockJenkins) {
+    let mut stuck = queue_item(41, "http://ci/job/team/job/api/");
+    stuck["stuck"] = json!(true);
+    stuck["why"] = json!("There are no nodes with the label 'gpu'");
+    mock.get(
+        "queue/api/json",
+        Reply::json(&json!({
+            "items": [
+                queue_item(40, "http://ci/job/team/job/api/"),
+                stuck,
+                queue_item(42, "http://ci/job/nightly/"),
+            ],
+        })),
+    );
+}
+
+#[test]
+fn list_queue() {
+    let mock = MockJenkins::start();
+    queue(&mock);
+
+    let out = mock.run(&["queue", "list"]);
+    out.assert_success();
+    let lines = out.stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with(&format!("{:<8}{:<40}", 40, "team/api")));
+    assert!(lines[1].contains("stuck"));
+    assert!(lines[1].ends_with("There are no nodes with the label 'gpu'"));
+    assert!(lines[2].starts_with(&format!("{:<8}{:<40}", 42, "nightly")));
+}
+
+#[test]
+fn cancel_queue_items() {
+    let mock = MockJenkins::start();
+    queue(&mock);
+    mock.post("queue/cancelItem", Reply::ok());
+
+    mock.run(&["queue", "cancel", "42"]).assert_success();
+    mock.run(&["queue", "cancel", "--job", "team/api"])
+        .assert_success();
+    mock.run(&["queue", "cancel", "--all-stuck"])
+        .assert_success();
+    let out = mock.run(&["queue", "cancel", "7"]);
+    assert!(out.stderr.contains("no matching items in the queue"));
+
+    assert_eq!(
+        mock.post_paths(),
+        [
+            "/queue/cancelItem?id=42",
+            "/queue/cancelItem?id=40",
+            "/queue/cancelItem?id=41",
+            "/queue/cancelItem?id=41",
+        ]
+    );
+}
+
+#[test]
+fn failed_cancellation() {
+    let mock = MockJenkins::start();
+    queue(&mock);
+    mock.post("queue/cancelItem?id=40", Reply::ok())
+        .post("queue/cancelItem?id=41", Reply::status(500));
+
+    let out = mock.run(&["queue", "cancel", "--job", "team/api"]);
+    out.assert_failure();
+    assert!(
+        out.stderr
+            .contains("failed to cancel queue item 41: 500 Internal Server Error"),
+        "{}",
+        out.stderr
+    );
+    assert!(out.stderr.contains("1 of 2 cancellations failed"));
+}
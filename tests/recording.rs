@@ -0,0 +1,236 @@
+mod common;
+
+use common::{job, MockJenkins, Reply, TOKEN};
+use serde_json::json;
+
+const FOLDER: &str = "com.cloudbees.hudson.plugins.folder.Folder";
+const FREESTYLE: &str = "hudson.model.FreeStyleProject";
+
+fn jenkins(mock: &MockJenkins) {
+    mock.get(
+        "api/json",
+        Reply::json(&json!({ "jobs": [job(FOLDER, "team"), job(FREESTYLE, "top")] })),
+    )
+    .get(
+        "job/team/api/json",
+        Reply::json(&json!({ "jobs": [job(FREESTYLE, "team/api")] })),
+    )
+    .get(
+        "job/team/job/api/5/artifact/*zip*/archive.zip",
+        Reply::bytes(b"PK\x03\x04\xff\x00"),
+    )
+    .post(
+        "quietDown",
+        Reply::status(403).body("No valid crumb was included in the request"),
+    )
+    .post("quietDown", Reply::ok())
+    .get(
+        "crumbIssuer/api/json",
+        Reply::json(&json!({ "crumb": "5f3a9c", "crumbRequestField": "Jenkins-Crumb" }))
+            .header("Set-Cookie", "JSESSIONID.1a2b=node0abc; Path=/"),
+    );
+}
+
+#[test]
+fn record_and_replay_a_session() {
+    let mock = MockJenkins::start();
+    jenkins(&mock);
+    let cassette = mock.dir().join("cassette");
+    let cassette = cassette.to_str().unwrap();
+
+    let listed = mock.run(&["--record", cassette, "job", "list"]);
+    listed.assert_success();
+    mock.run(&[
+        "--record",
+        &format!("{cassette}/artifact"),
+        "job",
+        "download",
+        "team/api",
+        "5",
+        "artifact",
+    ])
+    .assert_success();
+    mock.run(&["--record", &format!("{cassette}/csrf"), "shutdown", "on"])
+        .assert_success();
+
+    let mut files = std::fs::read_dir(cassette)
+        .unwrap()
+        .filter_map(|e| e.unwrap().file_name().into_string().ok())
+        .filter(|f| f.ends_with(".json"))
+        .collect::<Vec<_>>();
+    files.sort();
+    assert_eq!(
+        files,
+        [
+            "000001-GET-api-json.json",
+            "000002-GET-job-team-api-json.json"
+        ]
+    );
+
+    let recordings = [cassette, &format!("{cassette}/csrf")]
+        .iter()
+        .flat_map(|dir| std::fs::read_dir(dir).unwrap())
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().map_or(false, |e| e == "json"))
+        .map(|p| std::fs::read_to_string(p).unwrap())
+        .collect::<String>();
+    assert!(!recordings.contains(TOKEN));
+    assert!(!recordings.contains("Basic "));
+    assert!(!recordings.contains("node0abc"));
+    let retried: serde_json::Value = serde_json::from_slice(
+        &std::fs::read(format!("{cassette}/csrf/000003-POST-quietDown.json")).unwrap(),
+    )
+    .unwrap();
+    let headers = retried["request"]["headers"].as_array().unwrap();
+    assert!(headers.contains(&json!(["jenkins-crumb", "REDACTED"])));
+    assert!(headers.contains(&json!(["cookie", "REDACTED"])));
+
+    // Replies come from the recordings, the mock sees no new request
+    let requests = mock.requests().len();
+
+    let replayed = mock.run(&["--replay", cassette, "job", "list"]);
+    replayed.assert_success();
+    assert_eq!(replayed.stdout, listed.stdout);
+
+    std::fs::remove_file(mock.dir().join("api_5.zip")).unwrap();
+    mock.run(&[
+        "--replay",
+        &format!("{cassette}/artifact"),
+        "job",
+        "download",
+        "team/api",
+        "5",
+        "artifact",
+    ])
+    .assert_success();
+    assert_eq!(
+        std::fs::read(mock.dir().join("api_5.zip")).unwrap(),
+        b"PK\x03\x04\xff\x00"
+    );
+
+    mock.run(&["--replay", &format!("{cassette}/csrf"), "shutdown", "on"])
+        .assert_success();
+
+    assert_eq!(mock.requests().len(), requests);
+}
+
+#[test]
+fn replay_without_a_recorded_response() {
+    let mock = MockJenkins::start();
+    jenkins(&mock);
+    let cassette = mock.dir().join("cassette");
+    let cassette = cassette.to_str().unwrap();
+
+    mock.run(&["--record", cassette, "shutdown", "off"]);
+    let out = mock.run(&["--replay", cassette, "shutdown", "on"]);
+    out.assert_failure();
+    assert!(
+        out.stderr.contains(&format!(
+            "no recorded response for POST /quietDown in {cassette}"
+        )),
+        "{}",
+        out.stderr
+    );
+}
+
+#[test]
+fn record_into_an_existing_recording() {
+    let mock = MockJenkins::start();
+    jenkins(&mock);
+    let cassette = mock.dir().join("cassette");
+    let cassette = cassette.to_str().unwrap();
+
+    mock.run(&["--record", cassette, "job", "list"])
+        .assert_success();
+    let out = mock.run(&["--record", cassette, "job", "list"]);
+    out.assert_failure();
+    assert!(out.stderr.contains("already holds a recording"));
+
+    let out = mock.run(&["--record", cassette, "--replay", cassette, "job", "list"]);
+    out.assert_failure();
+    assert!(out.stderr.contains("cannot be used with"));
+}
+
+#[test]
+fn mask_secrets_of_a_recording() {
+    let mock = MockJenkins::start();
+    mock.post(
+        "me/descriptorByName/jenkins.security.ApiTokenProperty/generateNewToken",
+        Reply::json(&json!({
+            "status": "ok",
+            "data": { "tokenName": "ci", "tokenUuid": "9c1f", "tokenValue": "11ffee" },
+        })),
+    )
+    .post(
+        "me/descriptorByName/jenkins.security.ApiTokenProperty/revoke",
+        Reply::ok(),
+    )
+    .post(
+        "credentials/store/system/domain/_/createCredentials",
+        Reply::ok(),
+    )
+    .post("scriptText", Reply::text("done\n"));
+    let cassette = mock.dir().join("cassette");
+    let cassette = cassette.to_str().unwrap();
+
+    mock.run(&["--record", cassette, "token", "create", "ci"])
+        .assert_success();
+    mock.run_with_input(
+        &[
+            "--record",
+            &format!("{cassette}/credentials"),
+            "credentials",
+            "create",
+            "deploy",
+            "--type",
+            "username-password",
+            "--username",
+            "bob",
+        ],
+        "hunter2\n",
+    )
+    .assert_success();
+    mock.run(&[
+        "--record",
+        &format!("{cassette}/script"),
+        "script",
+        "run",
+        "-y",
+        "-e",
+        "println 'sk_live_42'",
+    ])
+    .assert_success();
+    mock.run(&[
+        "--record",
+        &format!("{cassette}/revoke"),
+        "token",
+        "revoke",
+        "-y",
+        "9c1f",
+    ])
+    .assert_success();
+
+    let recordings = ["", "/credentials", "/script", "/revoke"]
+        .iter()
+        .flat_map(|dir| std::fs::read_dir(format!("{cassette}{dir}")).unwrap())
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().map_or(false, |e| e == "json"))
+        .map(|p| std::fs::read_to_string(p).unwrap())
+        .collect::<String>();
+    for secret in ["11ffee", "hunter2", "sk_live_42", "9c1f", TOKEN] {
+        assert!(!recordings.contains(secret), "{secret} in {recordings}");
+    }
+    assert!(recordings.contains("<username>bob</username>"));
+
+    // Masked urls still match their requests
+    mock.run(&[
+        "--replay",
+        &format!("{cassette}/revoke"),
+        "token",
+        "revoke",
+        "-y",
+        "9c1f",
+    ])
+    .assert_success();
+    assert_eq!(mock.posts().len(), 4);
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use common::{computer, computers, job, MockJenkins, Reply, TOKEN};
+use serde_json::json;
+
+#[test]
+fn trace_requests() {
+    let mock = MockJenkins::start();
+    mock.get(
+        "api/json",
+        Reply::json(&json!({ "jobs": [job("hudson.model.FreeStyleProject", "api")] })),
+    );
+
+    let out = mock.run(&["job", "list"]);
+    out.assert_success();
+    assert!(!out.stderr.contains("jenkinsctl::trace"), "{}", out.stderr);
+
+    let out = mock.run(&["-v", "job", "list"]);
+    out.assert_success();
+    assert!(
+        out.stderr.contains(&format!(
+            "GET {}/api/json?tree=jobs[fullDisplayName,fullName,name] 200 OK ",
+            mock.url()
+        )),
+        "{}",
+        out.stderr
+    );
+    assert!(out.stderr.contains("1 requests in "));
+    assert!(!out.stderr.contains("> authorization"));
+    assert!(!out.stderr.contains("\"jobs\""));
+
+    let out = mock.run(&["-vvv", "job", "list"]);
+    out.assert_success();
+    assert!(
+        out.stderr.contains("> authorization: ***"),
+        "{}",
+        out.stderr
+    );
+    assert!(out.stderr.contains("< content-type: application/json"));
+    assert!(out.stderr.contains(r#"< {"jobs":[{"#));
+    assert!(!out.stderr.contains(TOKEN));
+}
+
+#[test]
+fn mask_secrets() {
+    let mock = MockJenkins::start();
+    mock.post(
+        "credentials/store/system/domain/_/createCredentials",
+        Reply::status(403).body("No valid crumb was included in the request"),
+    )
+    .post(
+        "credentials/store/system/domain/_/createCredentials",
+        Reply::ok(),
+    )
+    .get(
+        "crumbIssuer/api/json",
+        Reply::json(&json!({ "crumb": "5f3a9c", "crumbRequestField": "Jenkins-Crumb" }))
+            .header("Set-Cookie", "JSESSIONID.1a2b=node0abc; Path=/"),
+    );
+
+    let out = mock.run_with_input(
+        &[
+            "-vvv",
+            "credentials",
+            "create",
+            "deploy",
+            "--type",
+            "username-password",
+            "--username",
+            "bob",
+        ],
+        "hunter2\n",
+    );
+    out.assert_success();
+    assert!(
+        out.stderr
+            .contains("<username>bob</username><password>***</password>"),
+        "{}",
+        out.stderr
+    );
+    assert!(out.stderr.contains(r#""crumb":"***""#));
+    assert!(out.stderr.contains("> jenkins-crumb: ***"));
+    assert!(out.stderr.contains("< set-cookie: ***"));
+    for secret in ["hunter2", "5f3a9c", "node0abc", TOKEN] {
+        assert!(!out.stderr.contains(secret), "{secret} in {}", out.stderr);
+    }
+
+    mock.post(
+        "me/descriptorByName/jenkins.security.ApiTokenProperty/revoke",
+        Reply::ok(),
+    );
+    let out = mock.run(&["-v", "token", "revoke", "-y", "9c1f"]);
+    out.assert_success();
+    assert!(out.stderr.contains("/revoke?tokenUuid=*** 200 OK"));
+    assert!(!out.stderr.contains("9c1f"));
+}
+
+#[test]
+fn summarize_the_slowest_calls() {
+    let mock = MockJenkins::start();
+    mock.get("computer/api/json", Reply::status(503))
+        .get("computer/api/json", Reply::json(&computers(&[])));
+
+    let out = mock.run(&["-v", "--retries", "1", "node", "list"]);
+    out.assert_success();
+    let summary = out
+        .stderr
+        .lines()
+        .skip_while(|l| !l.contains("2 requests in "))
+        .collect::<Vec<_>>();
+    assert_eq!(summary.len(), 3, "{}", out.stderr);
+    assert!(summary[1..]
+        .iter()
+        .all(|l| l.contains("ms 200 GET ") || l.contains("ms 503 GET ")));
+}
+
+#[test]
+fn summarize_a_failed_health_check() {
+    let mock = MockJenkins::start();
+    mock.get(
+        "computer/api/json",
+        Reply::json(&computers(&[computer("agent-1", &[], true)])),
+    );
+
+    let out = mock.run(&["-v", "node", "health", "--fail-on-warning"]);
+    assert_eq!(out.code, Some(1));
+    assert!(out.stderr.contains("1 requests in "), "{}", out.stderr);
+}
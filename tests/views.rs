@@ -0,0 +1,172 @@
+mod common;
+
+use common::{MockJenkins, Reply};
+use serde_json::json;
+
+#[test]
+fn list_views() {
+    let mock = MockJenkins::start();
+    mock.get(
+        "api/json",
+        Reply::json(&json!({
+            "views": [
+                { "_class": "hudson.model.AllView", "name": "all", "url": "http://ci/" },
+                { "_class": "hudson.model.ListView", "name": "ops", "url": "http://ci/view/ops/" },
+            ],
+        })),
+    )
+    .get("job/team/api/json", Reply::json(&json!({ "views": [] })));
+
+    let out = mock.run(&["view", "list"]);
+    out.assert_success();
+    assert_eq!(
+        out.stdout,
+        format!("{:<40}AllView\n{:<40}ListView\n", "all", "ops")
+    );
+
+    mock.run(&["view", "list", "--folder", "team"])
+        .assert_success();
+    let paths = mock
+        .requests()
+        .into_iter()
+        .map(|r| r.path)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        [
+            "/api/json?tree=views[name,url]",
+            "/job/team/api/json?tree=views[name,url]",
+        ]
+    );
+}
+
+#[test]
+fn show_view() {
+    let mock = MockJenkins::start();
+    mock.get(
+        "view/my%20view/api/json",
+        Reply::json(&json!({
+            "jobs": [
+                { "name": "api", "color": "blue" },
+                { "name": "web", "color": "red_anime" },
+                { "name": "team", "color": null },
+            ],
+        })),
+    );
+
+    let out = mock.run(&["view", "show", "my view"]);
+    out.assert_success();
+    assert_eq!(
+        out.stdout,
+        format!(
+            "{:.<40}success\n{:.<40}running\n{:.<40}-\n",
+            "api", "web", "team"
+        )
+    );
+}
+
+#[test]
+fn show_missing_view() {
+    let mock = MockJenkins::start();
+
+    let out = mock.run(&["view", "show", "nope"]);
+    out.assert_failure();
+    assert!(out.stderr.contains("404"));
+}
+
+#[test]
+fn create_views() {
+    let mock = MockJenkins::start();
+    mock.post("createView", Reply::ok())
+        .post("job/team/createView", Reply::ok());
+
+    mock.run(&["view", "create", "ops", "--regex", "deploy-.*", "--recurse"])
+        .assert_success();
+    mock.run(&["view", "create", "mine", "--type", "my", "--folder", "team"])
+        .assert_success();
+
+    let posts = mock.posts();
+    assert_eq!(posts[0].path, "/createView?name=ops");
+    assert!(posts[0]
+        .body
+        .starts_with("<hudson.model.ListView><name>ops</name>"));
+    assert!(posts[0]
+        .body
+        .contains("<includeRegex>deploy-.*</includeRegex><recurse>true</recurse>"));
+    assert_eq!(posts[1].path, "/job/team/createView?name=mine");
+    assert!(posts[1]
+        .body
+        .starts_with("<hudson.model.MyView><name>mine</name>"));
+}
+
+#[test]
+fn add_and_remove_jobs() {
+    let mock = MockJenkins::start();
+    mock.post("view/ops/addJobToView", Reply::ok())
+        .post("job/team/view/ops/removeJobFromView", Reply::ok());
+
+    mock.run(&["view", "add-job", "ops", "deploy prod"])
+        .assert_success();
+    mock.run(&["view", "remove-job", "ops", "api", "--folder", "team"])
+        .assert_success();
+
+    assert_eq!(
+        mock.post_paths(),
+        [
+            "/view/ops/addJobToView?name=deploy%20prod",
+            "/job/team/view/ops/removeJobFromView?name=api",
+        ]
+    );
+}
+
+#[test]
+fn get_and_apply_view_config() {
+    let mock = MockJenkins::start();
+    mock.post("view/ops/config.xml", Reply::ok());
+    let config = "<hudson.model.ListView><name>ops</name></hudson.model.ListView>";
+    mock.get("view/ops/config.xml", Reply::text(config));
+
+    let out = mock.run(&["view", "config", "get", "ops"]);
+    out.assert_success();
+    assert_eq!(out.stdout, config);
+
+    mock.run_with_input(&["view", "config", "apply", "ops", "-"], config)
+        .assert_success();
+    let posts = mock.posts();
+    assert_eq!(posts[0].path, "/view/ops/config.xml");
+    assert_eq!(posts[0].body, config);
+}
+
+#[test]
+fn delete_view() {
+    let mock = MockJenkins::start();
+    mock.post("view/ops/doDelete", Reply::ok());
+
+    let out = mock.run(&["view", "delete", "ops"]);
+    assert!(out.stderr.contains("view removal aborted"));
+    mock.run(&["view", "delete", "-y", "ops"]).assert_success();
+
+    assert_eq!(mock.post_paths(), ["/view/ops/doDelete"]);
+}
+
+#[test]
+fn changes_of_a_missing_view() {
+    let mock = MockJenkins::start();
+
+    let out = mock.run(&["view", "add-job", "nope", "api"]);
+    out.assert_failure();
+    assert!(
+        out.stderr
+            .contains("failed to add api to the view nope: 404 Not Found"),
+        "{}",
+        out.stderr
+    );
+
+    mock.post("view/team%20%231/doDelete", Reply::ok());
+    mock.run(&["view", "delete", "-y", "team #1"])
+        .assert_success();
+    assert_eq!(
+        mock.post_paths().last().unwrap(),
+        "/view/team%20%231/doDelete"
+    );
+}